@@ -0,0 +1,579 @@
+-- Runtime of page that defines behaviour of its output buffers.
+-- It's sent by page once per neovim session and cached in `package.loaded`,
+-- so each function could be overridden from user config, e.g.:
+--
+--     vim.api.nvim_create_autocmd('User', {
+--         pattern = 'PageOpen',
+--         once = true,
+--         callback = function()
+--             require('page').close = function() vim.cmd 'bdelete!' end
+--         end,
+--     })
+--
+-- Functions are always invoked through module table, so overrides take
+-- effect in already opened output buffers too
+
+local M = {}
+
+
+-- Echoes message after a short delay to not be cleared by redraw
+function M.echo_notification(message)
+    vim.defer_fn(function()
+        local msg = '-- [PAGE] ' .. message .. ' --'
+        vim.api.nvim_echo({ { msg, 'Comment' }, }, false, {})
+        vim.api.nvim_create_autocmd('CursorMoved', {
+            buffer = 0,
+            once = true,
+            command = 'echo'
+        })
+    end, 64)
+end
+
+
+function M.scroll_text_bound(top, message, movement)
+    local row, col, search
+    if top then
+        row, col, search = 1, 1, { '\\S', 'c' }
+    else
+        row, col, search = 9999999999, 9999999999, { '\\S', 'bc' }
+    end
+    vim.api.nvim_call_function('cursor', { row, col })
+    vim.api.nvim_call_function('search', search)
+    if movement ~= nil then
+        movement()
+    end
+    M.echo_notification(message)
+end
+
+
+function M.scroll(top, message)
+    vim.wo.scrolloff = 0
+    local movement
+    if top then
+        local key = vim.api
+            .nvim_replace_termcodes('z<CR>M', true, false, true)
+        movement = function()
+            vim.api.nvim_feedkeys(key, 'nx', false)
+        end
+    else
+        movement = function()
+            vim.api.nvim_feedkeys('z-M', 'nx', false)
+        end
+    end
+    M.scroll_text_bound(top, message, movement)
+    vim.wo.scrolloff = 999
+end
+
+
+-- Closes output buffer and also exits from neovim
+-- if there's no other buffers with meaningful content
+function M.close()
+    local buf = vim.api.nvim_get_current_buf()
+    if buf ~= vim.b.page_alternate_bufnr and
+        vim.api.nvim_buf_is_loaded(vim.b.page_alternate_bufnr)
+    then
+        vim.api.nvim_set_current_buf(vim.b.page_alternate_bufnr)
+    end
+    vim.api.nvim_buf_delete(buf, { force = true })
+    local exit = true
+    for _, b in ipairs(vim.api.nvim_list_bufs()) do
+        local bt = vim.api.nvim_buf_get_option(b, 'buftype')
+        if bt == '' or
+            bt == 'acwrite' or
+            bt == 'terminal' or
+            bt == 'prompt'
+        then
+            local bm = vim.api.nvim_buf_get_option(b, 'modified')
+            if bm then
+                exit = false
+                break
+            end
+            local bl = vim.api.nvim_buf_get_lines(b, 0, -1, false)
+            if #bl ~= 0 and bl[1] ~= '' and #bl > 1 then
+                exit = false
+                break
+            end
+        end
+    end
+    if exit then
+        vim.cmd 'qa!'
+    end
+end
+
+
+function M.map(key, rhs)
+    vim.keymap.set('n', key, rhs, { nowait = true, buffer = 0 })
+end
+
+
+-- Actions that could be bound to keys of not writable (without -w) buffers.
+-- Each one receives options of output buffer (nil on file buffers),
+-- so more actions could be added here and bound in `g:page_keymaps`
+M.actions = {
+    scroll_top = function()
+        M.scroll(true, 'in the beginning of scroll')
+    end,
+    scroll_bottom = function()
+        M.scroll(false, 'at the end of scroll')
+    end,
+    text_top = function()
+        M.scroll_text_bound(true, 'in the beginning')
+    end,
+    text_bottom = function()
+        M.scroll_text_bound(false, 'at the end')
+    end,
+    close = function()
+        M.close()
+    end,
+    half_page_up = function()
+        M.feed('<C-u>')
+    end,
+    half_page_down = function()
+        M.feed('<C-d>')
+    end,
+    bottom = function()
+        M.feed('G')
+    end,
+    fetch_more = function(opts)
+        M.fetch_lines(opts, vim.b.page_query_size * vim.v.count1)
+    end,
+    fetch_all = function(opts)
+        M.fetch_lines(opts, 9999)
+    end,
+    pause = function(opts)
+        M.pause(opts, vim.api.nvim_get_current_buf(), not vim.b.page_paused)
+    end,
+    rerun = function(opts)
+        M.rerun(opts)
+    end,
+    next_hunk = function()
+        M.jump_to_hunk(false)
+    end,
+    prev_hunk = function()
+        M.jump_to_hunk(true)
+    end,
+    open_hunk = function(opts)
+        M.open_hunk(opts)
+    end,
+}
+
+
+-- Keys bound to actions by default. Could be changed by `g:page_keymaps`
+-- where each action is mapped to key, list of keys or `false` to unbind it
+M.default_keymaps = {
+    scroll_top = 'I',
+    scroll_bottom = 'A',
+    text_top = 'i',
+    text_bottom = 'a',
+    close = 'q',
+    half_page_up = 'u',
+    half_page_down = 'd',
+    bottom = 'x',
+    fetch_more = 'r',
+    fetch_all = 'R',
+    pause = 'p',
+    rerun = 'c',
+    next_hunk = ']c',
+    prev_hunk = '[c',
+    open_hunk = 'o',
+}
+
+
+-- Actions that depend on options of output buffer
+-- are bound only when they're supported there
+function M.is_action_available(name, opts)
+    if name == 'fetch_more' or name == 'fetch_all' then
+        return opts ~= nil and opts.query_lines_count ~= 0
+    elseif name == 'pause' then
+        -- Diff buffer isn't connected with page process
+        return opts ~= nil and opts.channel ~= nil
+    elseif name == 'rerun' then
+        return opts ~= nil and opts.rerunnable
+    elseif name == 'next_hunk' or name == 'prev_hunk' or name == 'open_hunk' then
+        return opts ~= nil and opts.diff_root ~= nil
+    end
+    return true
+end
+
+
+function M.feed(keys)
+    local count = vim.v.count > 0 and tostring(vim.v.count) or ''
+    keys = vim.api.nvim_replace_termcodes(count .. keys, true, false, true)
+    vim.api.nvim_feedkeys(keys, 'n', false)
+end
+
+
+-- Maps keys of not writable (without -w) buffer
+function M.setup_keymaps(opts)
+    local keymaps = vim.tbl_extend(
+        'force',
+        M.default_keymaps,
+        vim.g.page_keymaps or {}
+    )
+    for name, keys in pairs(keymaps) do
+        if type(keys) == 'string' then
+            keys = { keys }
+        end
+        if keys and M.is_action_available(name, opts) then
+            for _, key in ipairs(keys) do
+                M.map(key, function()
+                    local action = M.actions[name]
+                    if action == nil then
+                        M.echo_notification('unknown action: ' .. name)
+                        return
+                    end
+                    action(opts)
+                end)
+            end
+        end
+    end
+end
+
+
+-- Sets options for better reading experience
+-- which are restored when output buffer is left
+function M.setup_buffer(alternate_bufnr)
+    vim.b.page_alternate_bufnr = alternate_bufnr
+    if vim.wo.scrolloff > 999 or vim.wo.scrolloff < 0 then
+        vim.g.page_scrolloff_backup = 0
+    else
+        vim.g.page_scrolloff_backup = vim.wo.scrolloff
+    end
+    vim.bo.scrollback, vim.wo.scrolloff, vim.wo.signcolumn, vim.wo.number =
+        100000, 999, 'no', false
+    vim.api.nvim_create_autocmd('BufEnter', {
+        buffer = 0,
+        callback = function() vim.wo.scrolloff = 999 end
+    })
+    vim.api.nvim_create_autocmd('BufLeave', {
+        buffer = 0,
+        callback = function() vim.wo.scrolloff = vim.g.page_scrolloff_backup end
+    })
+end
+
+
+-- Sends notification to page process which created output buffer.
+-- Returns false if page process has gone
+function M.notify(opts, notification, ...)
+    return pcall(vim.rpcnotify, opts.channel, notification, opts.page_id, ...)
+end
+
+
+-- Defines user command available only in provided buffer
+local function define_buffer_command(buf, name, fn, cmd_opts)
+    cmd_opts = vim.tbl_extend('force', { force = true }, cmd_opts or {})
+    vim.api.nvim_buf_create_user_command(buf, name, fn, cmd_opts)
+end
+
+
+function M.fetch_lines(opts, count)
+    if not M.notify(opts, 'page_fetch_lines', count) then
+        M.echo_notification 'closed'
+    end
+end
+
+
+function M.pause(opts, buf, paused)
+    local notification = paused and 'page_pause' or 'page_resume'
+    if M.notify(opts, notification) then
+        vim.api.nvim_buf_set_var(buf, 'page_paused', paused)
+    end
+end
+
+
+function M.rerun(opts)
+    M.notify(opts, 'page_rerun')
+end
+
+
+function M.lcd(dir)
+    vim.cmd('lcd ' .. vim.fn.fnameescape(dir))
+end
+
+
+-- Defines commands of output buffer which page reads into.
+-- Options are: channel, page_id, writable, query_lines_count,
+-- rerunnable and pwd (optional)
+function M.setup_output(opts)
+    local buf = vim.api.nvim_get_current_buf()
+
+    -- Group is cleared when output buffer is recreated by page itself
+    vim.api.nvim_create_autocmd('BufDelete', {
+        group = vim.api.nvim_create_augroup('PageOutput', { clear = false }),
+        buffer = buf,
+        callback = function()
+            M.notify(opts, 'page_buffer_closed')
+        end
+    })
+
+    if opts.query_lines_count ~= 0 then
+        vim.b.page_query_size = opts.query_lines_count
+        define_buffer_command(buf, 'Page', function(cmd)
+            M.fetch_lines(opts, tonumber(cmd.args))
+        end, { nargs = '?' })
+    end
+
+    define_buffer_command(buf, 'PagePause', function()
+        M.pause(opts, buf, true)
+    end)
+    define_buffer_command(buf, 'PageResume', function()
+        M.pause(opts, buf, false)
+    end)
+
+    if opts.rerunnable then
+        define_buffer_command(buf, 'PageRerun', function()
+            M.rerun(opts)
+        end)
+    end
+
+    if opts.pwd then
+        vim.b.page_lcd_backup = vim.fn.getcwd()
+        M.lcd(opts.pwd)
+        vim.api.nvim_create_autocmd('BufEnter', {
+            buffer = buf,
+            callback = function() M.lcd(opts.pwd) end
+        })
+        vim.api.nvim_create_autocmd('BufLeave', {
+            buffer = buf,
+            callback = function() M.lcd(vim.b.page_lcd_backup) end
+        })
+    end
+end
+
+
+-- Returns path of file (relative to diff root) and line
+-- number in it which corresponds to line of diff buffer.
+-- Returns nil when line isn't inside of hunk
+function M.hunk_location(lnum)
+    local line
+    for l = lnum, 1, -1 do
+        local text = vim.fn.getline(l)
+        if line == nil then
+            local _, old_count, start, count =
+                text:match('^@@+ %-(%d+),?(%d*) %+(%d+),?(%d*)')
+            if start then
+                -- Hunk ends when its line counts are consumed (count is
+                -- omitted when it's 1), then header of next file follows
+                local old_left = old_count == '' and 1 or tonumber(old_count)
+                local new_left = count == '' and 1 or tonumber(count)
+                local offset = 0
+                for i = l + 1, lnum do
+                    if old_left <= 0 and new_left <= 0 then
+                        return nil
+                    end
+                    local kind = vim.fn.getline(i):sub(1, 1)
+                    if kind == '-' then
+                        old_left = old_left - 1
+                    elseif kind == '+' then
+                        new_left = new_left - 1
+                    elseif kind ~= '\\' then
+                        old_left, new_left = old_left - 1, new_left - 1
+                    end
+                    -- Removed lines aren't present in file
+                    if i < lnum and kind ~= '-' and kind ~= '\\' then
+                        offset = offset + 1
+                    end
+                end
+                line = tonumber(start) + offset
+            elseif not text:match('^[ +%-\\]') and text ~= '' then
+                -- File header or other text outside of hunk
+                return nil
+            end
+        else
+            local path = text:match('^%+%+%+ (.+)$')
+            if path and vim.fn.getline(l - 1):match('^%-%-%- ') then
+                path = path:gsub('\t.*$', '')
+                if path == '/dev/null' then
+                    return nil
+                end
+                return path:gsub('^b/', ''), line
+            end
+            if text:match('^diff ') then
+                return nil
+            end
+        end
+    end
+    return nil
+end
+
+
+function M.jump_to_hunk(backward)
+    local flags = backward and 'bW' or 'W'
+    for _ = 1, vim.v.count1 do
+        if vim.fn.search('^@@', flags) == 0 then
+            M.echo_notification(backward and 'no previous hunk' or 'no next hunk')
+            return
+        end
+    end
+end
+
+
+-- Opens file under hunk at the corresponding line by `nv` connected to
+-- this neovim, as it's opened from terminal. Falls back to `:edit`
+-- when page is installed without `nv`
+function M.open_hunk(opts)
+    local path, line = M.hunk_location(vim.fn.line('.'))
+    if path == nil then
+        M.echo_notification 'no hunk under cursor'
+        return
+    end
+    local file = opts.diff_root .. '/' .. path
+    if vim.fn.executable('nv') == 0 then
+        vim.cmd('edit +' .. line .. ' ' .. vim.fn.fnameescape(file))
+        return
+    end
+    -- PTY is used to not read nv's stdin into buffer
+    vim.fn.jobstart({ 'nv', '-a', vim.v.servername, '-e', tostring(line), file }, {
+        pty = true,
+        on_exit = function(_, status)
+            if status ~= 0 then
+                M.echo_notification('cannot open ' .. path)
+            end
+        end,
+    })
+end
+
+
+-- Resolves paths like `a/src/x.rs` by `gf` from root of git repository
+function M.setup_diff(opts)
+    vim.b.page_diff_root = opts.diff_root
+    vim.opt_local.path:prepend(opts.diff_root)
+    vim.bo.includeexpr = [[substitute(v:fname, '^[ab]/', '', '')]]
+end
+
+
+-- Returns attributes of highlight group, where groups like
+-- `@keyword.rust` fall back to `@keyword` when aren't defined
+local function resolve_hl(name)
+    while name do
+        local hl
+        if vim.api.nvim_get_hl then
+            hl = vim.api.nvim_get_hl(0, { name = name, link = false })
+        else
+            local ok, by_name = pcall(vim.api.nvim_get_hl_by_name, name, true)
+            hl = ok and by_name or {}
+            hl.fg, hl.bg = hl.foreground, hl.background
+        end
+        if next(hl) ~= nil then
+            return hl
+        end
+        name = name:match('^(.+)%.[^.]+$')
+    end
+    return {}
+end
+
+
+-- Returns highlight group that's displayed at position: extmarks
+-- and semantic tokens are drawn over treesitter and syntax highlights
+local function hl_group_at(buf, row, col)
+    if vim.inspect_pos == nil then
+        local id = vim.fn.synIDtrans(vim.fn.synID(row + 1, col + 1, 1))
+        return id ~= 0 and vim.fn.synIDattr(id, 'name') or nil
+    end
+    local pos = vim.inspect_pos(buf, row, col, {
+        syntax = true,
+        treesitter = true,
+        extmarks = true,
+        semantic_tokens = true,
+    })
+    for _, items in ipairs { pos.extmarks, pos.semantic_tokens, pos.treesitter, pos.syntax } do
+        for i = #items, 1, -1 do
+            local group = items[i].hl_group or (items[i].opts and items[i].opts.hl_group)
+            if group then
+                return group
+            end
+        end
+    end
+    return nil
+end
+
+
+local function sgr(hl)
+    local codes = { '0' }
+    local function rgb(prefix, color)
+        local r, g, b = bit.rshift(color, 16), bit.band(bit.rshift(color, 8), 0xFF), bit.band(color, 0xFF)
+        table.insert(codes, string.format('%s;2;%d;%d;%d', prefix, r, g, b))
+    end
+    if hl.bold then table.insert(codes, '1') end
+    if hl.italic then table.insert(codes, '3') end
+    if hl.underline or hl.undercurl then table.insert(codes, '4') end
+    if hl.reverse then table.insert(codes, '7') end
+    if hl.strikethrough then table.insert(codes, '9') end
+    if hl.fg then rgb('38', hl.fg) end
+    if hl.bg then rgb('48', hl.bg) end
+    return '\27[' .. table.concat(codes, ';') .. 'm'
+end
+
+
+-- Writes current buffer to stdout with its highlighting converted into
+-- escape sequences. Invoked by page from headless neovim (see --nvim-render)
+function M.render(filetype, path)
+    local buf = vim.api.nvim_get_current_buf()
+    if path then
+        filetype = vim.filetype.match({ filename = path, buf = buf }) or filetype
+    end
+    if filetype and filetype ~= '' and filetype ~= 'pager' then
+        vim.bo[buf].filetype = filetype
+    end
+    local ok, parser = pcall(vim.treesitter.get_parser, buf)
+    if ok and parser then
+        parser:parse()
+    end
+
+    local out = {}
+    local lines = vim.api.nvim_buf_get_lines(buf, 0, -1, false)
+    for row, line in ipairs(lines) do
+        local current
+        for col = 0, #line - 1 do
+            local byte = line:byte(col + 1)
+            -- Continuation bytes and spaces are displayed with previous highlighting
+            if bit.band(byte, 0xC0) ~= 0x80 and byte ~= 32 then
+                local group = hl_group_at(buf, row - 1, col) or 'Normal'
+                if group ~= current then
+                    current = group
+                    table.insert(out, group == 'Normal' and '\27[0m' or sgr(resolve_hl(group)))
+                end
+            end
+            table.insert(out, string.char(byte))
+        end
+        table.insert(out, '\27[0m')
+        if row < #lines or vim.bo[buf].eol then
+            table.insert(out, '\n')
+        end
+    end
+    io.stdout:write(table.concat(out))
+    io.stdout:flush()
+end
+
+
+-- Saves content and metadata of instance buffer into file in state directory
+-- of page, so it could be recreated by `page --restore-instances`
+function M.persist_instance(inst_name)
+    local inst = (_G.page_instances or {})[inst_name]
+    if not inst or not inst.persist_path or not vim.api.nvim_buf_is_valid(inst.buf) then
+        return
+    end
+    local lines = vim.api.nvim_buf_get_lines(inst.buf, 0, -1, false)
+    while #lines > 0 and lines[#lines] == '' do
+        table.remove(lines)
+    end
+    local state = {
+        name = inst_name,
+        title = vim.fn.bufname(inst.buf),
+        filetype = vim.bo[inst.buf].filetype,
+        source_cmd = vim.b[inst.buf].page_source_cmd,
+        lines = lines,
+    }
+    vim.fn.mkdir(vim.fn.fnamemodify(inst.persist_path, ':h'), 'p')
+    vim.fn.writefile({ vim.fn.json_encode(state) }, inst.persist_path)
+end
+
+
+function M.persist_instances()
+    for inst_name in pairs(_G.page_instances or {}) do
+        M.persist_instance(inst_name)
+    end
+end
+
+
+return M
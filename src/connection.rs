@@ -1,7 +1,8 @@
 pub use crate::{
     io_handler::{
         PipeOrSocketHandler,
-        NotificationFromNeovim
+        NotificationFromNeovim,
+        Routes
     },
     io_pipe_or_socket::{
         PipeOrSocketWrite as IoWrite,
@@ -21,7 +22,7 @@ use tokio_util::compat::{
 };
 
 use std::{
-    path::Path,
+    path::{Path, PathBuf},
     process::ExitStatus
 };
 
@@ -116,9 +117,28 @@ pub struct NeovimConnection<Apis: From<Neovim<IoWrite>>> {
     pub channel: u64,
     pub initial_win_and_buf: (Window<IoWrite>, Buffer<IoWrite>),
     pub rx: tokio::sync::mpsc::Receiver<NotificationFromNeovim>,
+    routes: Routes,
     handle: tokio::task::JoinHandle<Result<(), Box<nvim_rs::error::LoopError>>>,
 }
 
+impl<Apis: From<Neovim<IoWrite>>> NeovimConnection<Apis> {
+    /// Returns receiver of notifications that neovim side
+    /// sends with provided route id instead of page_id
+    pub fn open_route(
+        &mut self,
+        route_id: String
+    ) -> tokio::sync::mpsc::Receiver<NotificationFromNeovim> {
+        let (tx, rx) = tokio::sync::mpsc::channel(16);
+
+        self.routes
+            .lock()
+            .expect("Cannot lock notification routes")
+            .insert(route_id, tx);
+
+        rx
+    }
+}
+
 /// Connects to parent neovim session or spawns
 /// a new neovim process and connects to it through socket.
 /// Replacement for `nvim_rs::Session::new_child()`,
@@ -134,9 +154,12 @@ pub async fn open<Apis: From<Neovim<IoWrite>>>(
 
     let (tx, rx) = tokio::sync::mpsc::channel(16);
 
+    let routes = Routes::default();
+
     let handler = PipeOrSocketHandler {
         page_id: page_id.to_string(),
-        tx
+        tx,
+        routes: routes.clone(),
     };
 
     let mut nvim_proc = None;
@@ -218,6 +241,7 @@ pub async fn open<Apis: From<Neovim<IoWrite>>>(
         channel,
         initial_win_and_buf: (initial_win, initial_buf),
         rx,
+        routes,
         handle
     }
 }
@@ -228,7 +252,16 @@ pub async fn open<Apis: From<Neovim<IoWrite>>>(
 pub async fn close_and_exit<Apis: From<Neovim<IoWrite>>>(
     nvim_connection: &mut NeovimConnection<Apis>
 ) -> ! {
-    log::trace!(target: "exit", "close and exit");
+    close_and_exit_with_status(nvim_connection, 0).await
+}
+
+
+/// The same as [close_and_exit] but exits with provided status code
+pub async fn close_and_exit_with_status<Apis: From<Neovim<IoWrite>>>(
+    nvim_connection: &mut NeovimConnection<Apis>,
+    status: i32
+) -> ! {
+    log::trace!(target: "exit", "close and exit with {status}");
 
     if let Some(ref mut process) = nvim_connection.nvim_proc {
         if !process.is_finished() {
@@ -245,7 +278,7 @@ pub async fn close_and_exit<Apis: From<Neovim<IoWrite>>>(
     log::logger()
         .flush();
 
-    std::process::exit(0)
+    std::process::exit(status)
 }
 
 
@@ -352,6 +385,100 @@ fn print_redirect_protection(tmp_dir: &Path) {
     println!("{}", d.to_string_lossy());
 }
 
+/// Window where page or nv opens buffer instead of the current one
+/// (splits are handled separately since they have sizes)
+#[derive(Debug)]
+pub enum WindowTarget {
+    /// Current window, when no other target is provided
+    Current,
+    /// Window returned by lua function which name is
+    /// set in `g:page_window_target` (--window-target)
+    Configured,
+    /// New tab (--tab)
+    Tab,
+    /// Window by its number in the current tab or by its id (--window)
+    Window(i64),
+    /// Window where buffer was opened last time, if it's visible
+    /// in the current tab (--window-reuse)
+    LastUsed,
+}
+
+impl WindowTarget {
+    pub fn new(
+        tab: bool,
+        window: Option<i64>,
+        window_reuse: bool,
+        window_target: bool
+    ) -> WindowTarget {
+        if tab {
+            WindowTarget::Tab
+        } else if let Some(win) = window {
+            WindowTarget::Window(win)
+        } else if window_reuse {
+            WindowTarget::LastUsed
+        } else if window_target {
+            WindowTarget::Configured
+        } else {
+            WindowTarget::Current
+        }
+    }
+
+
+    /// Returns lua code that focuses target window,
+    /// or nothing when the current window is target
+    pub fn focus_command(&self) -> Option<String> {
+        let cmd = match self {
+            WindowTarget::Current => return None,
+
+            // Errors of user function are reported, but buffer
+            // is opened in the current window anyway
+            WindowTarget::Configured => String::from(indoc::indoc! {"
+                local target = vim.g.page_window_target
+                if type(target) ~= 'string' or target == '' then
+                    return
+                end
+                local ok, win = pcall(function()
+                    return assert(loadstring('return ' .. target))()()
+                end)
+                if not ok then
+                    vim.notify('g:page_window_target failed: ' .. tostring(win), vim.log.levels.WARN)
+                elseif win and vim.api.nvim_win_is_valid(win) then
+                    vim.api.nvim_set_current_win(win)
+                end
+            "}),
+
+            WindowTarget::Tab => String::from("vim.cmd 'tab split'\n"),
+
+            // Window ids start from 1000, so smaller values are numbers
+            WindowTarget::Window(win) => indoc::formatdoc! {"
+                local win = {win}
+                if win < 1000 then
+                    win = vim.fn.win_getid(win)
+                end
+                if win == 0 or not vim.api.nvim_win_is_valid(win) then
+                    error 'No window {win}'
+                end
+                vim.api.nvim_set_current_win(win)
+            "},
+
+            WindowTarget::LastUsed => String::from(indoc::indoc! {"
+                local win = vim.g.page_last_window
+                if win and vim.api.nvim_win_is_valid(win) and
+                    vim.api.nvim_win_get_tabpage(win) == vim.api.nvim_get_current_tabpage()
+                then
+                    vim.api.nvim_set_current_win(win)
+                end
+            "}),
+        };
+
+        Some(cmd)
+    }
+}
+
+/// Lua code that stores window where page or nv opened buffer for --window-reuse
+pub const REMEMBER_WINDOW: &str = "vim.g.page_last_window = vim.api.nvim_get_current_win()\n";
+
+
 /// Spawns child neovim process on top of page,
 /// which further will be connected to page with UNIX socket.
 /// In this way neovim UI is displayed properly on top of page,
@@ -393,8 +520,12 @@ fn spawn_child_nvim_process(
 
     let term = current_term();
 
+    // Variables set by programs that invoked page as $PAGER shouldn't
+    // leak into terminals of neovim, where they'd affect later page runs
     std::process::Command::new("nvim")
         .args(&nvim_args)
+        .env_remove("GIT_PAGER_IN_USE")
+        .env_remove("MAN_PN")
         .stdin(term)
         .spawn()
         .expect("Cannot spawn a child neovim process")
@@ -415,11 +546,9 @@ fn current_term() -> std::fs::File {
 }
 
 
-/// Returns path to custom neovim config if
-/// it's present in a corresponding locations
-fn default_config_path() -> Option<String> {
-    use std::path::PathBuf;
-
+/// Returns directory where page looks up its configs:
+/// $XDG_CONFIG_HOME/page or $HOME/.config/page
+pub fn config_dir() -> Option<PathBuf> {
     let page_home = std::env::var("XDG_CONFIG_HOME")
         .map(|xdg_config_home| {
             PathBuf::from(xdg_config_home)
@@ -434,9 +563,35 @@ fn default_config_path() -> Option<String> {
 
     log::trace!(target: "config", "directory is: {page_home:?}");
 
-    let Ok(page_home) = page_home else {
-        return None;
-    };
+    page_home.ok()
+}
+
+
+/// Returns directory where page keeps data between neovim sessions:
+/// $XDG_STATE_HOME/page or $HOME/.local/state/page
+pub fn state_dir() -> Option<PathBuf> {
+    let page_state = std::env::var("XDG_STATE_HOME")
+        .map(|xdg_state_home| {
+            PathBuf::from(xdg_state_home)
+                .join("page")
+        });
+
+    let page_state = page_state.or_else(|_| std::env::var("HOME")
+        .map(|home| {
+            PathBuf::from(home)
+                .join(".local/state/page")
+        }));
+
+    log::trace!(target: "state", "directory is: {page_state:?}");
+
+    page_state.ok()
+}
+
+
+/// Returns path to custom neovim config if
+/// it's present in a corresponding locations
+pub fn default_config_path() -> Option<String> {
+    let page_home = config_dir()?;
 
     let init_lua = page_home
         .join("init.lua");
@@ -527,11 +682,21 @@ mod io_pipe_or_socket {
 mod io_handler {
     use super::{io_pipe_or_socket::PipeOrSocketWrite, Neovim, Value};
 
+    /// Senders of notifications addressed to additional buffers
+    /// opened by the same page process. Keys are route ids
+    /// which are passed by neovim side instead of page_id
+    pub type Routes = std::sync::Arc<
+        std::sync::Mutex<
+            std::collections::HashMap<String, tokio::sync::mpsc::Sender<NotificationFromNeovim>>
+        >
+    >;
+
     /// Receives and collects notifications from neovim side over IPC or TCP/IP
     #[derive(Clone)]
     pub struct PipeOrSocketHandler {
         pub tx: tokio::sync::mpsc::Sender<NotificationFromNeovim>,
         pub page_id: String,
+        pub routes: Routes,
     }
 
     #[async_trait::async_trait]
@@ -561,13 +726,31 @@ mod io_handler {
                 .get(0)
                 .and_then(Value::as_str);
 
-            let same_page_id = page_id
-                .map_or(false, |page_id| page_id == self.page_id);
-            if !same_page_id {
-                log::warn!(target: "invalid page id", "{page_id:?}");
+            let tx = match page_id {
+                Some(page_id) if page_id == self.page_id => self.tx.clone(),
 
-                return
-            }
+                Some(route_id) => {
+                    let route = self.routes
+                        .lock()
+                        .expect("Cannot lock notification routes")
+                        .get(route_id)
+                        .cloned();
+
+                    let Some(tx) = route else {
+                        log::warn!(target: "invalid page id", "{page_id:?}");
+
+                        return
+                    };
+
+                    tx
+                }
+
+                None => {
+                    log::warn!(target: "invalid page id", "{page_id:?}");
+
+                    return
+                }
+            };
 
             let notification_from_neovim = match notification.as_str() {
                 "page_fetch_lines" => {
@@ -583,6 +766,18 @@ mod io_handler {
                 "page_buffer_closed" => {
                     NotificationFromNeovim::BufferClosed
                 },
+                "page_pause" => {
+                    NotificationFromNeovim::Pause
+                },
+                "page_resume" => {
+                    NotificationFromNeovim::Resume
+                },
+                "page_rerun" => {
+                    NotificationFromNeovim::Rerun
+                },
+                "page_clear" => {
+                    NotificationFromNeovim::Clear
+                },
 
                 unknown => {
                     log::warn!(target: "unhandled notification", "{unknown}");
@@ -591,10 +786,12 @@ mod io_handler {
                 }
             };
 
-            self.tx
+            if let Err(e) = tx
                 .send(notification_from_neovim)
                 .await
-                .expect("Cannot receive notification");
+            {
+                log::info!(target: "notification", "Receiver has gone: {e}");
+            }
         }
     }
 
@@ -606,5 +803,9 @@ mod io_handler {
         FetchPart,
         FetchLines(usize),
         BufferClosed,
+        Pause,
+        Resume,
+        Rerun,
+        Clear,
     }
 }
@@ -1,5 +1,6 @@
 use clap::{
     Parser,
+    Subcommand,
     ArgGroup,
     ArgAction,
     ValueHint,
@@ -44,11 +45,30 @@ pub struct Options {
     pub command_post: Option<String>,
 
     /// Run lua expr on output buffer after it was created
-    /// or connected as instance {n}
-    /// ~ ~ ~
+    /// or connected as instance
     #[clap(display_order=107, long="E")]
     pub lua_post: Option<String>,
 
+    /// Use options from [profile.<PROFILE>] table of config file over
+    /// its top-level options (both are overridden by provided flags)
+    /// [file: $XDG_CONFIG_HOME/page/page.toml; default: detected from
+    /// $GIT_PAGER_IN_USE, $MAN_PN or name of parent process]
+    #[clap(display_order=108, long="profile", env="PAGE_PROFILE")]
+    pub profile: Option<String>,
+
+    /// Print which profile was selected and why into stderr
+    #[clap(display_order=109, long="profile-debug")]
+    pub profile_debug: bool,
+
+    /// Translate flags of `less` provided after this flag and from $LESS
+    /// into page options (-F to -O, -S to nowrap, +G to -f, +/<PATTERN>
+    /// to search etc.) and ignore others that have no meaning for page
+    /// [page options should be provided before; implied for all
+    /// flags when page is invoked as `less`] {n}
+    /// ~ ~ ~
+    #[clap(display_order=110, long="less-compat")]
+    pub less_compat: bool,
+
     /// Create output buffer with <INSTANCE> tag or use existed
     /// with replacing its content by text from page's stdin
     #[clap(display_order=200, short='i')]
@@ -60,11 +80,43 @@ pub struct Options {
     pub instance_append: Option<String>,
 
     /// Close  output buffer with <INSTANCE_CLOSE> tag if it exists
-    /// [without other flags revokes implied by defalt -o or -p option] {n}
-    /// ~ ~ ~
+    /// [without other flags revokes implied by defalt -o or -p option]
     #[clap(display_order=202, short='x')]
     pub instance_close: Option<String>,
 
+    /// Print name, buffer number, PTY device path, line count of each
+    /// instance output buffer and whether some page process writes into it
+    /// [without other flags revokes implied by defalt -o or -p option]
+    #[clap(display_order=203, long="list-instances")]
+    pub instances_list: bool,
+
+    /// Print instance output buffers listed by --list-instances as JSON
+    #[clap(display_order=204, long="json", requires="instances_list")]
+    pub json: bool,
+
+    /// Close all instance output buffers
+    /// [without other flags revokes implied by defalt -o or -p option]
+    #[clap(display_order=205, long="close-all-instances")]
+    pub instances_close_all: bool,
+
+    /// Recreate instance output buffers which content was saved into
+    /// $XDG_STATE_HOME/page when neovim exited or their writer disconnected
+    /// (to append to them by -I after neovim restart)
+    /// [without other flags revokes implied by defalt -o or -p option]
+    #[clap(display_order=206, long="restore-instances")]
+    pub instances_restore: bool,
+
+    /// Print path of named pipe which appends text written into it to output
+    /// buffer with <INSTANCE> tag (to redirect text cheaply and repeatedly):
+    /// a background page process that listens it is spawned if not running yet
+    /// [requires -a or $NVIM; page exits without other actions] {n}
+    /// ~ ~ ~
+    #[clap(display_order=207, long="fifo-path", value_name="INSTANCE")]
+    pub fifo_path: Option<String>,
+
+    #[clap(long="fifo-listen", hide = true, requires = "instance_append")]
+    pub fifo_listen: Option<String>,
+
     /// Create and use output buffer (to redirect text from page's stdin)
     /// [implied by default unless -x and/or <FILE> provided without
     /// other flags]
@@ -133,6 +185,9 @@ pub struct Options {
     #[clap(flatten)]
     pub output: OutputOptions,
 
+    #[clap(subcommand)]
+    pub ctl: Option<Ctl>,
+
 
     #[clap(skip)]
     output_implied: once_cell::unsync::OnceCell<bool>,
@@ -157,11 +212,22 @@ impl Options {
             self.output.command.is_some() ||
             self.output.lua.is_some() ||
             self.output.pwd ||
+            !self.output.exec.is_empty() ||
+            self.output.man ||
+            self.output.diff ||
             self.output.filetype != "pager"
         )
     }
 
 
+    pub fn is_instances_management_requested(&self) -> bool {
+        self.instance_close.is_some() ||
+        self.instances_list ||
+        self.instances_close_all ||
+        self.instances_restore
+    }
+
+
     pub fn is_output_split_implied(&self) -> bool {
         *self.output_split_implied.get_or_init(||
             self.output.split.split_left_cols.is_some() ||
@@ -221,6 +287,13 @@ pub struct OutputOptions {
     #[clap(display_order=1, short='O')]
     pub noopen_lines: Option<Option<isize>>,
 
+    /// Highlight text printed by -O in headless neovim with user's
+    /// config, colorscheme and treesitter (to look the same as in
+    /// output buffer) [slower; bundled syntaxes or bat are used
+    /// if neovim fails]
+    #[clap(display_order=1, long="nvim-render")]
+    pub nvim_render: bool,
+
     /// Read no more than <QUERY_LINES> from page's stdin:
     /// next lines should be fetched by invoking
     /// :Page <QUERY> command or 'r'/'R' keypress on neovim side
@@ -232,12 +305,58 @@ pub struct OutputOptions {
     #[clap(display_order=4, short='q')]
     pub query_lines: Option<Option<isize>>,
 
+    /// Run <EXEC> command with shell and redirect its stdout and stderr
+    /// into output buffer instead of page's stdin (to rerun it by invoking
+    /// :PageRerun command or 'c' keypress on neovim side)
+    /// [-O, -q and -z are ignored; could be repeated:
+    /// each next command is displayed in its own buffer opened in split below]
+    #[clap(display_order=13, long="exec", action=ArgAction::Append)]
+    pub exec: Vec<String>,
+
+    /// Read named pipe at <PATH> into its own output buffer titled by <NAME>
+    /// opened in split below, concurrently with other inputs
+    /// [could be repeated; without other flags revokes implied by default -o or -p option]
+    #[clap(display_order=18, long="fifo", value_name="NAME=PATH", action=ArgAction::Append, value_parser=parse_named_fifo)]
+    pub fifo: Vec<NamedFifo>,
+
+    /// Rerun <EXEC> command each <WATCH> seconds replacing content
+    /// of output buffer (like `watch`) until it will be closed
+    #[clap(display_order=14, long="watch", requires="exec", value_parser=parse_seconds)]
+    pub watch: Option<f64>,
+
+    /// Highlight lines that differs from previous <EXEC> command run
+    #[clap(display_order=15, long="differences", requires="exec")]
+    pub differences: bool,
+
+    /// Redirect stderr of <EXEC> command into separate output buffer
+    /// opened in split below instead of highlighting it in red
+    #[clap(display_order=16, long="stderr-split", requires="exec")]
+    pub stderr_split: bool,
+
+    /// Exit with the same status as <EXEC> command as soon as it's finished
+    /// (with --watch: as its last run when output buffer will be closed)
+    #[clap(display_order=17, long="exit-status", requires="exec")]
+    pub exit_status: bool,
+
+    /// Read man page from page's stdin into plain buffer with ft=man
+    /// instead of output buffer (to set as $MANPAGER and navigate
+    /// references like in :Man) [-O, -q, -z and -p are ignored]
+    #[clap(display_order=19, long="man", conflicts_with_all=["exec", "fifo"])]
+    pub man: bool,
+
+    /// Read diff from page's stdin into plain buffer with ft=diff (or git)
+    /// instead of output buffer: paths are resolved from root of git repo,
+    /// hunks are navigated by ']c' '[c' and opened in editor by 'o'
+    /// (to set as git's core.pager) [-O, -q, -z and -p are ignored]
+    #[clap(display_order=20, long="diff", conflicts_with_all=["exec", "fifo", "man"])]
+    pub diff: bool,
+
     /// Set filetype on output buffer (to enable syntax highlighting)
     /// [pager: default; not works with text echoed by -O]
     #[clap(display_order=7, short='t', default_value="pager", hide_default_value=true)]
     pub filetype: String,
 
-    /// Do not remap i, I, a, A, u, d, x, q (and r, R with -q) keys
+    /// Do not map keys from g:page_keymaps (i, I, a, A, u, d, x, q, p and r, R with -q; c with --exec by default)
     /// [wouldn't unmap on connected instance output buffer]
     #[clap(display_order=11, short='w')]
     pub writable: bool,
@@ -247,6 +366,12 @@ pub struct OutputOptions {
     #[clap(display_order=3, short='P')]
     pub pwd: bool,
 
+    #[clap(long="less-commands", hide = true)]
+    pub less_commands: Option<String>,
+
+    #[clap(long="less-pattern", hide = true)]
+    pub less_pattern: Option<String>,
+
 
     #[clap(flatten)]
     pub split: SplitOptions,
@@ -290,10 +415,48 @@ pub struct SplitOptions {
     pub split_below_rows: Option<u8>,
 
     /// With any of -r -l -u -d -R -L -U -D open floating window instead of split
-    /// [to not overwrite data in the current terminal] {n}
-    /// ~ ~ ~
+    /// [to not overwrite data in the current terminal]
     #[clap(display_order=908, short='+')]
     pub popup: bool,
+
+    /// Open in a new tab instead of the current window
+    #[clap(display_order=909, long="tab")]
+    pub tab: bool,
+
+    /// Open in window with <WINDOW> number in the current tab or with <WINDOW> id
+    #[clap(display_order=910, long="window")]
+    pub window: Option<i64>,
+
+    /// Open in window used previously by page or nv if it's in the current tab
+    #[clap(display_order=911, long="window-reuse")]
+    pub window_reuse: bool,
+
+    /// Open in window returned by lua function which name is set in g:page_window_target {n}
+    /// ~ ~ ~
+    #[clap(display_order=912, long="window-target")]
+    pub window_target: bool,
+}
+
+impl SplitOptions {
+    /// Returns options of split below with default ratio, in which
+    /// named inputs and stderr buffer are opened next to output buffer
+    pub fn below() -> SplitOptions {
+        SplitOptions {
+            split_left: 0,
+            split_right: 0,
+            split_above: 0,
+            split_below: 1,
+            split_left_cols: None,
+            split_right_cols: None,
+            split_above_rows: None,
+            split_below_rows: None,
+            popup: false,
+            tab: false,
+            window: None,
+            window_reuse: false,
+            window_target: false,
+        }
+    }
 }
 
 
@@ -329,12 +492,41 @@ fn splits_arg_group() -> ArgGroup {
             "split_above_rows",
             "split_below_rows"
         ])
+        .args([
+            "tab",
+            "window",
+            "window_reuse",
+            "window_target"
+        ])
         .multiple(false)
 }
 
 
-pub fn get_options() -> Options {
-    Options::parse()
+fn parse_seconds(s: &str) -> Result<f64, String> {
+    let secs = s.parse::<f64>()
+        .map_err(|e| e.to_string())?;
+
+    if !secs.is_finite() || secs <= 0.0 {
+        return Err(String::from("should be a positive number of seconds"))
+    }
+
+    Ok(secs)
+}
+
+
+fn parse_named_fifo(s: &str) -> Result<NamedFifo, String> {
+    let Some((name, path)) = s.split_once('=') else {
+        return Err(String::from("should be provided as NAME=PATH"))
+    };
+
+    if name.is_empty() || path.is_empty() {
+        return Err(String::from("both NAME and PATH should be non-empty"))
+    }
+
+    Ok(NamedFifo {
+        name: String::from(name),
+        path: String::from(path),
+    })
 }
 
 
@@ -369,6 +561,110 @@ impl From<&std::ffi::OsStr> for FileOption {
     }
 }
 
+/// Actions on existed instance buffers
+#[derive(Subcommand, Debug)]
+pub enum Ctl {
+    /// Act on existed instance buffer from shell (to orchestrate
+    /// dashboards of instance buffers from scripts)
+    /// [requires address: -a or $NVIM]
+    Ctl {
+        /// Name of instance buffer (see -i and -I)
+        instance: String,
+
+        #[clap(subcommand)]
+        action: CtlAction,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum CtlAction {
+    /// Move cursor to the last line in windows that display instance buffer
+    ScrollBottom,
+
+    /// Move cursor to the first line in windows that display instance buffer
+    ScrollTop,
+
+    /// Remove content of instance buffer (like -i does). When page
+    /// still writes into it, that page recreates buffer and continues
+    Clear,
+
+    /// Switch to window that displays instance buffer
+    /// or display it in the current window
+    Focus,
+
+    /// Set title of instance buffer
+    Rename {
+        title: String,
+    },
+
+    /// Set filetype on instance buffer
+    SetFiletype {
+        filetype: String,
+    },
+
+    /// Write content of instance buffer into file at <PATH>
+    Save {
+        #[clap(value_hint=ValueHint::FilePath)]
+        path: String,
+    },
+}
+
+
+#[derive(Debug, Clone)]
+pub struct NamedFifo {
+    pub name: String,
+    pub path: String,
+}
+
+
+/// Input that's read into its own output buffer
+/// concurrently with stdin or the first <EXEC> command
+#[derive(Debug, Clone)]
+pub enum NamedInput {
+    Exec(String),
+    Fifo(NamedFifo),
+}
+
+impl NamedInput {
+    pub fn name(&self) -> &str {
+        match self {
+            NamedInput::Exec(cmd) => cmd,
+            NamedInput::Fifo(NamedFifo { name, .. }) => name,
+        }
+    }
+}
+
+
+impl OutputOptions {
+    /// Returns command which output is displayed in main output buffer
+    pub fn main_exec(&self) -> Option<&str> {
+        self.exec
+            .first()
+            .map(String::as_str)
+    }
+
+
+    /// Returns inputs that should be displayed in additional
+    /// output buffers: each <EXEC> except of the first one and each <FIFO>
+    pub fn named_inputs(&self) -> Vec<NamedInput> {
+        let execs = self.exec
+            .iter()
+            .skip(1)
+            .cloned()
+            .map(NamedInput::Exec);
+
+        let fifos = self.fifo
+            .iter()
+            .cloned()
+            .map(NamedInput::Fifo);
+
+        execs
+            .chain(fifos)
+            .collect()
+    }
+}
+
+
 impl FileOption {
     pub fn as_str(&self) -> &str {
         let (FileOption::Uri(s) | FileOption::Path(s)) = self;
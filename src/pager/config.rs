@@ -0,0 +1,373 @@
+/// A module that reads default options from page.toml config file.
+/// Its entries are converted into flags which are added to provided
+/// ones unless the same flags are provided, so they take precedence
+use crate::cli::Options;
+use clap::{
+    parser::ValueSource,
+    ArgAction,
+    ArgMatches,
+    CommandFactory,
+    FromArgMatches,
+    Parser,
+};
+use std::{ffi::OsString, path::PathBuf};
+
+
+/// Parses cli options merged over options from config file
+pub fn get_options() -> Options {
+    let mut provided_args = std::env::args_os()
+        .collect::<Vec<_>>();
+
+    if crate::less::is_enabled(&provided_args) {
+        provided_args = crate::less::translate_args(provided_args);
+    }
+
+    let provided = Options::command()
+        .get_matches_from(&provided_args);
+
+    let profile = match provided.get_one::<String>("profile") {
+        Some(profile) => Some(Profile {
+            name: profile.clone(),
+            reason: String::from("provided by --profile or $PAGE_PROFILE"),
+            detected: false,
+        }),
+
+        None => detect_profile(),
+    };
+
+    let (config, profile_applied) = read_config(profile.as_ref());
+
+    if provided.get_flag("profile_debug") {
+        match (&profile, profile_applied) {
+            (Some(Profile { name, reason, .. }), true) =>
+                eprintln!("page: profile '{name}' is applied: {reason}"),
+
+            (Some(Profile { name, reason, .. }), _) =>
+                eprintln!("page: profile '{name}' isn't configured: {reason}"),
+
+            (None, _) =>
+                eprintln!("page: no profile is matched"),
+        }
+    }
+
+    // Config is applied only on output buffers
+    if config.is_empty() || provided.subcommand().is_some() {
+        return Options::from_arg_matches(&provided)
+            .unwrap_or_else(|e| e.exit())
+    }
+
+    let mut args = provided_args;
+    let config_args = config_to_args(config, &provided);
+    log::trace!(target: "config", "append args: {config_args:?}");
+
+    // Flags with optional values (-O, -q, -z) might consume a
+    // provided positional argument, so config flags are appended
+    let end = args
+        .iter()
+        .position(|arg| arg == "--")
+        .unwrap_or(args.len());
+    args.splice(end..end, config_args);
+
+    Options::parse_from(args)
+}
+
+
+/// Profiles that are used unless the same
+/// profiles are defined in config file
+const BUILTIN_PROFILES: &str = indoc::indoc! {r#"
+    [git]
+    filetype = "git"
+
+    [man]
+    man = true
+
+    [journalctl]
+    filetype = "messages"
+
+    [psql]
+    noopen_lines = true
+
+    [systemctl]
+    noopen_lines = true
+"#};
+
+
+#[derive(Debug)]
+struct Profile {
+    name: String,
+    reason: String,
+    detected: bool,
+}
+
+
+/// Selects profile by env variables set by programs that use page
+/// as $PAGER or by name of parent process (also skipping shell)
+fn detect_profile() -> Option<Profile> {
+    let detected = |name: &str, reason: String| Some(Profile {
+        name: String::from(name),
+        reason,
+        detected: true,
+    });
+
+    let (pid, comm) = invoking_process()?;
+
+    // These variables are inherited also by everything that's run from
+    // terminals of neovim spawned by page, so they're trusted only
+    // when page is invoked by the program that sets them
+    if comm == "git" && std::env::var_os("GIT_PAGER_IN_USE").is_some() {
+        return detected("git", format!("$GIT_PAGER_IN_USE is set by '{comm}' process ({pid})"))
+    }
+
+    if comm == "man" && std::env::var_os("MAN_PN").is_some() {
+        return detected("man", format!("$MAN_PN is set by '{comm}' process ({pid})"))
+    }
+
+    detected(&comm, format!("invoked by '{comm}' process ({pid})"))
+}
+
+
+/// Returns pid and name of the nearest parent process that isn't shell
+fn invoking_process() -> Option<(u32, String)> {
+    const SHELLS: [&str; 7] = ["sh", "bash", "dash", "zsh", "fish", "ksh", "ash"];
+
+    let mut pid = parent_pid(std::process::id())?;
+    for _ in 0..2 {
+        let comm = read_process_name(pid)?;
+
+        if !SHELLS.contains(&comm.as_str()) {
+            return Some((pid, comm))
+        }
+
+        pid = parent_pid(pid)?;
+    }
+
+    None
+}
+
+
+#[cfg(target_os = "linux")]
+fn parent_pid(pid: u32) -> Option<u32> {
+    let stat = std::fs::read_to_string(format!("/proc/{pid}/stat"))
+        .ok()?;
+
+    // Process name is enclosed in parens and might contain spaces
+    stat.rsplit_once(')')?
+        .1
+        .split_whitespace()
+        .nth(1)?
+        .parse()
+        .ok()
+}
+
+#[cfg(target_os = "linux")]
+fn read_process_name(pid: u32) -> Option<String> {
+    let comm = std::fs::read_to_string(format!("/proc/{pid}/comm"))
+        .ok()?;
+
+    Some(String::from(comm.trim_end()))
+}
+
+#[cfg(not(target_os = "linux"))]
+fn parent_pid(_pid: u32) -> Option<u32> {
+    None
+}
+
+#[cfg(not(target_os = "linux"))]
+fn read_process_name(_pid: u32) -> Option<String> {
+    None
+}
+
+
+/// Returns path of page.toml located near to init.vim/init.lua
+fn config_file_path() -> Option<PathBuf> {
+    connection::config_dir()
+        .map(|page_home| page_home.join("page.toml"))
+}
+
+
+type ConfigTable = toml::value::Table;
+
+/// Reads top-level options from config file and replaces them by options
+/// from selected profile. Returns also whether profile was applied
+fn read_config(profile: Option<&Profile>) -> (ConfigTable, bool) {
+    let mut config = read_config_file()
+        .unwrap_or_default();
+
+    let mut profiles = match config.remove("profile") {
+        Some(toml::Value::Table(profiles)) => profiles,
+
+        Some(v) => {
+            log::warn!(target: "config", "[profile] should be a table, not: {v}");
+
+            ConfigTable::new()
+        }
+
+        None => ConfigTable::new(),
+    };
+
+    let mut config = normalize_keys(config);
+
+    let Some(profile) = profile else {
+        return (config, false)
+    };
+
+    let profile_config = profiles
+        .remove(&profile.name)
+        .or_else(|| BUILTIN_PROFILES
+            .parse::<toml::Value>()
+            .expect("Cannot parse builtin profiles")
+            .as_table_mut()?
+            .remove(&profile.name));
+
+    let Some(toml::Value::Table(profile_config)) = profile_config else {
+        if !profile.detected {
+            log::warn!(target: "config", "Profile '{}' not found", profile.name);
+        }
+
+        return (config, false)
+    };
+
+    let profile_config = normalize_keys(profile_config);
+    let cmd = Options::command();
+
+    // Profile's option replaces top-level options from the same group
+    for key in profile_config.keys() {
+        for sibling in group_siblings(&cmd, key) {
+            config.remove(&sibling);
+        }
+    }
+
+    config.extend(profile_config);
+
+    (config, true)
+}
+
+
+fn read_config_file() -> Option<ConfigTable> {
+    let config_path = config_file_path()?;
+
+    let content = match std::fs::read_to_string(&config_path) {
+        Ok(content) => content,
+
+        Err(e) => {
+            if e.kind() != std::io::ErrorKind::NotFound {
+                log::warn!(target: "config", "Cannot read {config_path:?}: {e}");
+            }
+
+            return None
+        }
+    };
+
+    match content.parse::<toml::Value>() {
+        Ok(toml::Value::Table(config)) => Some(config),
+
+        Ok(_) => None,
+
+        Err(e) => {
+            log::error!(target: "config", "Cannot parse {config_path:?}: {e}");
+
+            None
+        }
+    }
+}
+
+
+/// Options could be set also by their long names, e.g. "list-instances"
+fn normalize_keys(config: ConfigTable) -> ConfigTable {
+    config
+        .into_iter()
+        .map(|(key, value)| (key.replace('-', "_"), value))
+        .collect()
+}
+
+
+/// Returns ids of options that can't be used together with option of provided id
+fn group_siblings(cmd: &clap::Command, id: &str) -> Vec<String> {
+    cmd.get_groups()
+        .filter(|group| group
+            .get_args()
+            .any(|arg_id| arg_id == id))
+        .flat_map(|group| group
+            .get_args()
+            .map(|arg_id| arg_id.to_string()))
+        .collect()
+}
+
+
+/// Converts config entries into flags skipping options that were
+/// provided explicitly, also together with options which are in
+/// the same group with provided ones (e.g. splits)
+fn config_to_args(config: ConfigTable, provided: &ArgMatches) -> Vec<OsString> {
+    let cmd = Options::command();
+
+    let is_provided = |id: &str| matches!(
+        provided.value_source(id),
+        Some(ValueSource::CommandLine | ValueSource::EnvVariable)
+    );
+
+    let is_group_provided = |id: &str| group_siblings(&cmd, id)
+        .iter()
+        .any(|sibling| is_provided(sibling));
+
+    let mut args = vec![];
+
+    for (key, value) in config {
+        let Some(arg) = cmd
+            .get_arguments()
+            .find(|arg| arg.get_id() == key.as_str())
+            .filter(|arg| !arg.is_positional() && !arg.is_hide_set())
+        else {
+            log::warn!(target: "config", "Unknown option: {key}");
+            continue
+        };
+
+        if key == "profile" || key == "profile_debug" || key == "less_compat" || is_provided(&key) || is_group_provided(&key) {
+            log::trace!(target: "config", "{key} is overridden");
+            continue
+        }
+
+        let flag = match (arg.get_long(), arg.get_short()) {
+            (Some(long), _) => format!("--{long}"),
+            (None, Some(short)) => format!("-{short}"),
+            (None, None) => continue,
+        };
+
+        let values = match value {
+            toml::Value::Array(values) => values,
+            value => vec![value],
+        };
+
+        for value in values {
+            match value {
+                toml::Value::Boolean(true) => args.push(OsString::from(&flag)),
+
+                toml::Value::Boolean(false) => {}
+
+                toml::Value::Integer(count) if matches!(arg.get_action(), ArgAction::Count) => {
+                    for _ in 0..count {
+                        args.push(OsString::from(&flag))
+                    }
+                }
+
+                toml::Value::Integer(i) => {
+                    args.push(OsString::from(&flag));
+                    args.push(OsString::from(i.to_string()));
+                }
+
+                toml::Value::Float(f) => {
+                    args.push(OsString::from(&flag));
+                    args.push(OsString::from(f.to_string()));
+                }
+
+                toml::Value::String(s) => {
+                    args.push(OsString::from(&flag));
+                    args.push(OsString::from(s));
+                }
+
+                v => log::warn!(target: "config", "Unsupported value of {key}: {v}"),
+            }
+        }
+    }
+
+    args
+}
@@ -14,36 +14,64 @@ pub mod gather_env {
         pub prefetch_usage: PrefetchLinesUsage,
         pub query_lines_count: usize,
         pub input_from_pipe: bool,
+        pub source_pid: Option<u32>,
+        pub source_cmd: Option<String>,
     }
 
     pub fn enter() -> Env {
-        let input_from_pipe = !atty::is(atty::Stream::Stdin);
+        let stdin_from_pipe = !atty::is(atty::Stream::Stdin);
 
-        let opt = parse_and_alter_opts(input_from_pipe);
+        let opt = parse_and_alter_opts(stdin_from_pipe);
 
-        let (term_height, prefetch_usage) = determine_prefetch_usage(
+        // Output of --exec command and named pipe
+        // listened in background are also read from pipe
+        let input_from_pipe = stdin_from_pipe ||
+            !opt.output.exec.is_empty() ||
+            opt.fifo_listen.is_some();
+
+        // Terminal might be unavailable (e.g. for page listening named pipe
+        // in background), so its dimensions are requested only when needed
+        let term_dimensions = TermDimensions::new(|| {
+            term_size::dimensions()
+                .expect("Cannot get terminal dimensions")
+        });
+
+        let prefetch_usage = determine_prefetch_usage(
             opt.output.noopen_lines,
             opt.pagerize,
             &opt.files,
-            input_from_pipe
+            input_from_pipe,
+            &term_dimensions
         );
 
         let query_lines_count = determine_query_lines_count(
             opt.output.query_lines,
-            term_height
+            &term_dimensions
         );
 
+        let mut source_pid = None;
+        let mut source_cmd = opt.output
+            .main_exec()
+            .map(String::from);
+        if source_cmd.is_none() && stdin_from_pipe && opt.fifo_listen.is_none() {
+            source_pid = find_pipe_producer_pid();
+            source_cmd = source_pid
+                .and_then(read_process_command_line);
+        }
+
         Env {
             opt,
             prefetch_usage,
             query_lines_count,
             input_from_pipe,
+            source_pid,
+            source_cmd,
         }
     }
 
 
     fn parse_and_alter_opts(input_from_pipe: bool) -> crate::cli::Options {
-        let mut opt = crate::cli::get_options();
+        let mut opt = crate::config::get_options();
 
         // Remove some arguments from pagerized invocation
         if opt.pagerize_hidden.is_some() {
@@ -67,34 +95,47 @@ pub mod gather_env {
             opt.address = None;
         }
 
-        // Override -O by -o, -p and -x flags and when page don't read from pipe
+        // Override -O by -o, -p, -x and --fifo-path flags and when page don't read from pipe
         if opt.output_open ||
             opt.pty_path_print ||
-            opt.instance_close.is_some() ||
+            opt.is_instances_management_requested() ||
+            opt.fifo_path.is_some() ||
             (!input_from_pipe && opt.files.len() != 1)
         {
             opt.output.noopen_lines = None;
         }
 
+        // Command will be executed on output buffer or named
+        // pipe will be listened, so page's stdin isn't read
+        if !opt.output.exec.is_empty() || opt.fifo_listen.is_some() {
+            opt.output.noopen_lines = None;
+            opt.output.query_lines = None;
+            opt.pagerize = None;
+        }
+
+        // Man page and diff are read from page's stdin into plain buffer
+        if opt.output.man || opt.output.diff {
+            opt.output.noopen_lines = None;
+            opt.output.query_lines = None;
+            opt.pagerize = None;
+            opt.pty_path_print = false;
+        }
+
         opt
     }
 
 
-    type TermHeight = usize;
+    type TermDimensions = once_cell::unsync::Lazy<(usize, usize)>;
 
     fn determine_prefetch_usage(
         noopen_lines: Option<Option<isize>>,
         pagerize: Option<Option<usize>>,
         files: &Vec<crate::cli::FileOption>,
-        input_from_pipe: bool
-    ) -> (TermHeight, PrefetchLinesUsage) {
+        input_from_pipe: bool,
+        term_dimensions: &TermDimensions
+    ) -> PrefetchLinesUsage {
         use once_cell::unsync::Lazy;
 
-        let term_dimensions = Lazy::new(|| {
-            term_size::dimensions()
-                .expect("Cannot get terminal dimensions")
-        });
-
         let (term_width, term_height) = (
             Lazy::new(|| term_dimensions.0),
             Lazy::new(|| term_dimensions.1),
@@ -142,25 +183,116 @@ pub mod gather_env {
             }
         }
 
-        (*term_height, prefetch_usage)
+        prefetch_usage
     }
 
 
     fn determine_query_lines_count(
         query_lines: Option<Option<isize>>,
-        term_height: TermHeight,
+        term_dimensions: &TermDimensions,
     ) -> usize {
         match query_lines {
             Some(Some(positive_number @ 0..)) => positive_number as usize,
-            Some(Some(negative_number)) => term_height
+            Some(Some(negative_number)) => term_dimensions.1
                 .saturating_sub(negative_number.unsigned_abs()),
-            Some(None) => term_height
+            Some(None) => term_dimensions.1
                 .saturating_sub(3),
             None => 0,
         }
     }
 
 
+    /// Finds process that writes into page's stdin: it's the one
+    /// which holds the other end of the same pipe opened for writing
+    #[cfg(target_os = "linux")]
+    fn find_pipe_producer_pid() -> Option<u32> {
+        let stdin_pipe = std::fs::read_link("/proc/self/fd/0")
+            .ok()?;
+        if !stdin_pipe.to_string_lossy().starts_with("pipe:") {
+            return None
+        }
+
+        let self_pid = std::process::id();
+
+        let mut producers = vec![];
+        for proc_dir in std::fs::read_dir("/proc").ok()?.flatten() {
+            let pid = proc_dir
+                .file_name()
+                .to_str()
+                .and_then(|pid| pid.parse::<u32>().ok());
+            let Some(pid) = pid.filter(|pid| *pid != self_pid) else {
+                continue
+            };
+
+            let Ok(fds) = std::fs::read_dir(proc_dir.path().join("fd")) else {
+                continue
+            };
+
+            for fd in fds.flatten() {
+                let same_pipe = std::fs::read_link(fd.path())
+                    .map_or(false, |link| link == stdin_pipe);
+
+                if same_pipe && is_opened_for_writing(&proc_dir.path(), &fd.file_name()) {
+                    producers.push(pid);
+                    break
+                }
+            }
+        }
+        log::trace!(target: "producer", "{stdin_pipe:?}: {producers:?}");
+
+        // Children might inherit pipe from producer, so pick the one with lowest pid
+        producers
+            .into_iter()
+            .min()
+    }
+
+    #[cfg(target_os = "linux")]
+    fn is_opened_for_writing(
+        proc_dir: &std::path::Path,
+        fd: &std::ffi::OsStr
+    ) -> bool {
+        let Ok(fdinfo) = std::fs::read_to_string(proc_dir.join("fdinfo").join(fd)) else {
+            return false
+        };
+
+        let flags = fdinfo
+            .lines()
+            .find_map(|ln| ln.strip_prefix("flags:"))
+            .and_then(|flags| u32::from_str_radix(flags.trim(), 8).ok());
+
+        // O_WRONLY or O_RDWR
+        flags.map_or(false, |flags| flags & 0o3 != 0)
+    }
+
+    /// Returns command line of process with quoted arguments
+    #[cfg(target_os = "linux")]
+    fn read_process_command_line(pid: u32) -> Option<String> {
+        let cmdline = std::fs::read(format!("/proc/{pid}/cmdline"))
+            .ok()?;
+
+        let args = cmdline
+            .split(|b| *b == b'\0')
+            .filter(|arg| !arg.is_empty())
+            .map(String::from_utf8_lossy);
+
+        let cmd = shell_words::join(args);
+        log::trace!(target: "producer", "{pid}: {cmd}");
+
+        Some(cmd)
+            .filter(|cmd| !cmd.is_empty())
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    fn find_pipe_producer_pid() -> Option<u32> {
+        None
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    fn read_process_command_line(_pid: u32) -> Option<String> {
+        None
+    }
+
+
     #[derive(Debug)]
     pub enum PrefetchLinesUsage {
         Enabled {
@@ -191,6 +323,8 @@ pub mod check_usage {
         pub query_lines_count: usize,
         pub input_from_pipe: bool,
         pub print_protection: bool,
+        pub source_pid: Option<u32>,
+        pub source_cmd: Option<String>,
     }
 
     impl Usage {
@@ -226,6 +360,8 @@ pub mod check_usage {
             input_from_pipe,
             opt,
             query_lines_count,
+            source_pid,
+            source_cmd,
             ..
         } = env_ctx;
 
@@ -252,6 +388,8 @@ pub mod check_usage {
             query_lines_count,
             input_from_pipe,
             print_protection,
+            source_pid,
+            source_cmd,
         }
     }
 
@@ -303,6 +441,8 @@ pub mod connect_neovim {
         pub outp_buf_usage: OutputBufferUsage,
         pub nvim_child_proc_spawned: bool,
         pub input_from_pipe: bool,
+        pub source_pid: Option<u32>,
+        pub source_cmd: Option<String>,
     }
 
     impl Neovim {
@@ -332,6 +472,8 @@ pub mod connect_neovim {
             page_id,
             prefetched_lines,
             query_lines_count,
+            source_pid,
+            source_cmd,
             ..
         } = cli_ctx;
 
@@ -344,8 +486,9 @@ pub mod connect_neovim {
         let outp_buf_usage = determine_output_buffer_usage(
             opt.is_output_split_implied(),
             opt.is_output_implied(),
-            &opt.instance_close,
+            opt.is_instances_management_requested(),
             &opt.files,
+            &opt.output.fifo,
             input_from_pipe
         );
 
@@ -357,6 +500,8 @@ pub mod connect_neovim {
             inst_usage,
             outp_buf_usage,
             input_from_pipe,
+            source_pid,
+            source_cmd,
             nvim_child_proc_spawned: false,
         }
     }
@@ -388,8 +533,9 @@ pub mod connect_neovim {
     fn determine_output_buffer_usage(
         is_output_split_implied: bool,
         is_output_implied: bool,
-        instance_close: &Option<String>,
+        is_instances_management_requested: bool,
         files: &Vec<crate::cli::FileOption>,
+        fifos: &[crate::cli::NamedFifo],
         input_from_pipe: bool,
     ) -> OutputBufferUsage {
         let mut outp_buf_usage = OutputBufferUsage::Disabled;
@@ -397,7 +543,7 @@ pub mod connect_neovim {
         if is_output_split_implied {
             outp_buf_usage = OutputBufferUsage::CreateSplit;
         } else if input_from_pipe || is_output_implied ||
-            (instance_close.is_none() && files.is_empty())
+            (!is_instances_management_requested && files.is_empty() && fifos.is_empty())
         {
             outp_buf_usage = OutputBufferUsage::CreateSubstituting;
         }
@@ -468,6 +614,8 @@ pub mod output_buffer_available {
         pub print_output_buf_pty: bool,
         pub page_id: u128,
         pub pagerized_page_size: Option<usize>,
+        pub source_pid: Option<u32>,
+        pub source_cmd: Option<String>,
     }
 
     impl Output {
@@ -502,6 +650,8 @@ pub mod output_buffer_available {
             prefetched_lines,
             query_lines_count,
             page_id,
+            source_pid,
+            source_cmd,
             ..
         } = nvim_ctx;
 
@@ -528,6 +678,8 @@ pub mod output_buffer_available {
             print_output_buf_pty,
             page_id,
             pagerized_page_size,
+            source_pid,
+            source_cmd,
         }
     }
 
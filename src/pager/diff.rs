@@ -0,0 +1,51 @@
+//! A module that helps to display diff read from page's stdin
+//! in plain buffer with navigable hunks and paths (see --diff)
+use std::path::PathBuf;
+
+
+/// Returns root of git repository found from $PWD to which paths of diff
+/// are relative. Falls back to $PWD when it's not in git repository
+pub fn repo_root() -> PathBuf {
+    let pwd = std::env::var("PWD")
+        .map(PathBuf::from)
+        .or_else(|_| std::env::current_dir())
+        .unwrap_or_default();
+
+    let rev_parse = std::process::Command::new("git")
+        .arg("rev-parse")
+        .arg("--show-toplevel")
+        .current_dir(&pwd)
+        .stderr(std::process::Stdio::null())
+        .output();
+
+    match rev_parse {
+        Ok(out) if out.status.success() => {
+            let root = String::from_utf8_lossy(&out.stdout);
+
+            PathBuf::from(root.trim_end())
+        }
+
+        Ok(out) => {
+            log::trace!(target: "diff", "Not a git repository: {}", out.status);
+
+            pwd
+        }
+
+        Err(e) => {
+            log::warn!(target: "diff", "Cannot run git: {e}");
+
+            pwd
+        }
+    }
+}
+
+
+/// Output of `git log -p` and `git show` contains commit headers
+/// which are highlighted only with `ft=git`
+pub fn detect_filetype(first_line: &str) -> &'static str {
+    if first_line.starts_with("commit ") {
+        "git"
+    } else {
+        "diff"
+    }
+}
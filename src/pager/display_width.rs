@@ -0,0 +1,262 @@
+//! A module that measures how text read byte by byte would be displayed
+//! in terminal to count rows that it takes when wrapped (see -O),
+//! and that strips escape sequences from text of plain buffers
+use unicode_width::UnicodeWidthChar;
+
+
+const TAB_STOP: usize = 8;
+
+
+/// Tracks column of the current row respecting multibyte and wide
+/// characters, tab stops and escape sequences which take no space
+pub struct LineWidth {
+    term_width: usize,
+    column: usize,
+    escape: Escape,
+    utf8: Vec<u8>,
+}
+
+enum Escape {
+    None,
+    Started,
+    /// `ESC [ ... final byte` e.g. SGR colors
+    Csi,
+    /// `ESC ] ... BEL` or `ESC ] ... ESC \` e.g. hyperlinks
+    Osc,
+    OscTerminator,
+}
+
+impl LineWidth {
+    pub fn new(term_width: usize) -> LineWidth {
+        LineWidth {
+            term_width: term_width.max(1),
+            column: 0,
+            escape: Escape::None,
+            utf8: Vec::with_capacity(4),
+        }
+    }
+
+
+    /// Should be invoked when line ends
+    pub fn reset(&mut self) {
+        self.column = 0;
+        self.escape = Escape::None;
+        self.utf8.clear();
+    }
+
+
+    /// Returns true if provided byte completes a character
+    /// that doesn't fit into the current row, so it's wrapped
+    /// and starts the next one
+    pub fn push(&mut self, b: u8) -> bool {
+        if self.escape.consume(b) {
+            return false
+        }
+
+        let char_width = match b {
+            b'\t' => {
+                let next_tab_stop = (self.column / TAB_STOP + 1) * TAB_STOP;
+                self.column = next_tab_stop.min(self.term_width);
+                return false
+            }
+
+            b'\r' => {
+                self.column = 0;
+                return false
+            }
+
+            0x08 => {
+                self.column = self.column.saturating_sub(1);
+                return false
+            }
+
+            0x00..=0x7F => {
+                self.utf8.clear();
+                usize::from(!b.is_ascii_control())
+            }
+
+            _ => {
+                // Continuation byte without leading one
+                if self.utf8.is_empty() && b & 0b1100_0000 == 0b1000_0000 {
+                    return self.advance(1)
+                }
+
+                if b & 0b1100_0000 != 0b1000_0000 {
+                    self.utf8.clear();
+                }
+                self.utf8.push(b);
+
+                match std::str::from_utf8(&self.utf8) {
+                    Ok(s) => {
+                        let w = s
+                            .chars()
+                            .next()
+                            .and_then(UnicodeWidthChar::width)
+                            .unwrap_or(0);
+                        self.utf8.clear();
+                        w
+                    }
+
+                    // Character isn't complete yet
+                    Err(e) if e.error_len().is_none() => return false,
+
+                    Err(_) => {
+                        self.utf8.clear();
+                        1
+                    }
+                }
+            }
+        };
+
+        self.advance(char_width)
+    }
+
+
+    // Wrap occurs only when the next character is displayed,
+    // so line that fits exactly takes one row
+    fn advance(&mut self, char_width: usize) -> bool {
+        if char_width == 0 {
+            return false
+        }
+
+        if self.column + char_width > self.term_width {
+            self.column = char_width;
+            return true
+        }
+
+        self.column += char_width;
+        false
+    }
+}
+
+
+impl Escape {
+    /// Returns true if byte is a part of escape sequence
+    fn consume(&mut self, b: u8) -> bool {
+        *self = match self {
+            Escape::None if b == 0x1B => Escape::Started,
+            Escape::None => return false,
+
+            Escape::Started => match b {
+                b'[' => Escape::Csi,
+                b']' => Escape::Osc,
+                _ => Escape::None,
+            },
+
+            Escape::Csi if (0x40..=0x7E).contains(&b) => Escape::None,
+            Escape::Csi => Escape::Csi,
+
+            Escape::Osc => match b {
+                0x07 => Escape::None,
+                0x1B => Escape::OscTerminator,
+                _ => Escape::Osc,
+            },
+
+            Escape::OscTerminator => match b {
+                b'\\' => Escape::None,
+                _ => Escape::Osc,
+            },
+        };
+
+        true
+    }
+}
+
+
+/// Removes escape sequences (colors, hyperlinks, etc.) from text
+/// that should be displayed in plain buffer instead of terminal
+pub fn strip_escapes(text: &str) -> String {
+    let mut escape = Escape::None;
+
+    let plain = text
+        .bytes()
+        .filter(|&b| !escape.consume(b))
+        .collect::<Vec<_>>();
+
+    String::from_utf8_lossy(&plain)
+        .into_owned()
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Counts rows that text takes in terminal of provided width
+    fn rows(text: &str, term_width: usize) -> usize {
+        let mut line_width = LineWidth::new(term_width);
+
+        1 + text
+            .bytes()
+            .filter(|b| line_width.push(*b))
+            .count()
+    }
+
+
+    #[test]
+    fn wraps_by_display_width() {
+        let cases = [
+            ("abcd", 4, 1),
+            ("abcde", 4, 2),
+            ("abcdefghi", 4, 3),
+            ("日本", 4, 1),
+            ("日本語", 4, 2),
+            ("a日本", 4, 2),
+            ("e\u{301}e\u{301}e\u{301}e\u{301}", 4, 1),
+            ("e\u{301}e\u{301}e\u{301}e\u{301}e\u{301}", 4, 2),
+            ("\u{200b}abcd", 4, 1),
+            ("\tab", 10, 1),
+            ("\tab", 8, 2),
+            ("abcd\rabcd", 4, 1),
+            ("abcde\x08", 5, 1),
+            ("ab\x08\x08abcd", 4, 1),
+        ];
+
+        for (text, term_width, expected) in cases {
+            assert_eq!(rows(text, term_width), expected, "{text:?} in {term_width}");
+        }
+    }
+
+
+    #[test]
+    fn escape_sequences_take_no_space() {
+        let cases = [
+            ("\x1B[1;31mabcd\x1B[0m", 4, 1),
+            ("\x1B[38;2;255;0;0mabcde\x1B[m", 4, 2),
+            ("\x1B]8;;https://example.com\x07abcd\x1B]8;;\x07", 4, 1),
+            ("\x1B]8;;https://example.com\x1B\\abcd\x1B]8;;\x1B\\", 4, 1),
+        ];
+
+        for (text, term_width, expected) in cases {
+            assert_eq!(rows(text, term_width), expected, "{text:?} in {term_width}");
+        }
+    }
+
+
+    #[test]
+    fn invalid_utf8_takes_one_column_per_byte() {
+        let mut line_width = LineWidth::new(2);
+
+        let wrapped = [0xFF, 0x80, 0xFE]
+            .into_iter()
+            .map(|b| line_width.push(b))
+            .collect::<Vec<_>>();
+
+        assert_eq!(wrapped, [false, false, true]);
+    }
+
+
+    #[test]
+    fn strips_escape_sequences() {
+        let cases = [
+            ("plain", "plain"),
+            ("\x1B[1;31mred\x1B[0m text", "red text"),
+            ("\x1B]8;;https://example.com\x07link\x1B]8;;\x1B\\", "link"),
+            ("日本\x1B[K語", "日本語"),
+        ];
+
+        for (text, expected) in cases {
+            assert_eq!(strip_escapes(text), expected, "{text:?}");
+        }
+    }
+}
@@ -0,0 +1,335 @@
+/// A module that runs command provided with --exec
+/// and streams its output line by line
+use std::{
+    io::{BufRead, BufReader, Read},
+    process::{ExitStatus, Stdio},
+    time::{Duration, Instant},
+};
+use tokio::{
+    io::{AsyncBufReadExt, AsyncRead},
+    process::Child,
+};
+
+
+/// This struct holds spawned command together with
+/// receiver of lines read from its stdout and stderr
+pub struct Execution {
+    child: Child,
+    started_at: Instant,
+    pub lines: tokio::sync::mpsc::Receiver<Line>,
+}
+
+impl Execution {
+    pub fn pid(&self) -> Option<u32> {
+        self.child.id()
+    }
+
+
+    pub fn elapsed(&self) -> Duration {
+        self.started_at.elapsed()
+    }
+
+
+    pub async fn wait(&mut self) -> std::io::Result<ExitStatus> {
+        self.child.wait().await
+    }
+
+
+    /// Kills command which is still running (e.g. to rerun it)
+    pub async fn kill(&mut self) {
+        if let Err(e) = self.child.kill().await {
+            log::warn!(target: "exec", "Cannot kill command: {e}");
+        }
+    }
+}
+
+
+/// Spawns command with shell. Its stdout and stderr are read
+/// on separate tasks which will wait when lines aren't received,
+/// so the command will be blocked on write too. Command is
+/// killed when execution is dropped
+pub fn spawn(cmd: &str) -> std::io::Result<Execution> {
+    log::trace!(target: "exec", "{cmd}");
+
+    #[cfg(windows)]
+    let (shell, shell_cmd_flag) = ("cmd", "/C");
+    #[cfg(not(windows))]
+    let (shell, shell_cmd_flag) = ("sh", "-c");
+
+    let started_at = Instant::now();
+    let mut child = tokio::process::Command::new(shell)
+        .arg(shell_cmd_flag)
+        .arg(cmd)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()?;
+
+    let (tx, lines) = tokio::sync::mpsc::channel(64);
+
+    let stdout = child.stdout
+        .take()
+        .expect("Cannot get stdout of executed command");
+    read_lines_on_task(stdout, Line::Stdout, tx.clone());
+
+    let stderr = child.stderr
+        .take()
+        .expect("Cannot get stderr of executed command");
+    read_lines_on_task(stderr, Line::Stderr, tx);
+
+    Ok(Execution {
+        child,
+        started_at,
+        lines,
+    })
+}
+
+
+/// Returns exit code of finished command. When command
+/// was terminated by signal it's reported as shells do: 128 + signal
+pub fn exit_code(status: ExitStatus) -> i32 {
+    #[cfg(unix)]
+    if let Some(signal) = std::os::unix::process::ExitStatusExt::signal(&status) {
+        return 128 + signal
+    }
+
+    status.code()
+        .unwrap_or(1)
+}
+
+
+/// Opens input and reads its lines on separate thread, then sends
+/// them tagged by stream. Error of opening is sent as stderr line
+pub fn read_lines_on_thread<R: Read, F: FnOnce() -> std::io::Result<R> + Send + 'static>(
+    open: F,
+    stream: fn(Vec<u8>) -> Line,
+    tx: tokio::sync::mpsc::Sender<Line>
+) {
+    std::thread::spawn(move || {
+        let input = match open() {
+            Ok(input) => input,
+            Err(e) => {
+                log::error!(target: "exec", "Cannot open input: {e}");
+
+                let msg = format!("Cannot open input: {e}\n");
+                if let Err(e) = tx.blocking_send(Line::Stderr(msg.into_bytes())) {
+                    log::info!(target: "exec", "Receiver has gone: {e}");
+                }
+
+                return
+            }
+        };
+
+        let mut input = BufReader::new(input);
+
+        loop {
+            let mut ln = Vec::with_capacity(2048);
+
+            match input.read_until(b'\n', &mut ln) {
+                Ok(0) => break,
+
+                Ok(_) => {
+                    if tx.blocking_send(stream(ln)).is_err() {
+                        break
+                    }
+                }
+
+                Err(e) => {
+                    log::warn!(target: "exec", "Error reading line: {e}");
+
+                    break
+                }
+            }
+        }
+    });
+}
+
+
+/// Reads lines of executed command on separate task
+/// and sends them tagged by stream
+fn read_lines_on_task<R: AsyncRead + Unpin + Send + 'static>(
+    input: R,
+    stream: fn(Vec<u8>) -> Line,
+    tx: tokio::sync::mpsc::Sender<Line>
+) {
+    tokio::spawn(async move {
+        let mut input = tokio::io::BufReader::new(input);
+
+        loop {
+            let mut ln = Vec::with_capacity(2048);
+
+            match input.read_until(b'\n', &mut ln).await {
+                Ok(0) => break,
+
+                Ok(_) => {
+                    if tx.send(stream(ln)).await.is_err() {
+                        break
+                    }
+                }
+
+                Err(e) => {
+                    log::warn!(target: "exec", "Error reading line: {e}");
+
+                    break
+                }
+            }
+        }
+    });
+}
+
+
+/// Line read from executed command tagged by stream it came from
+#[derive(Debug)]
+pub enum Line {
+    Stdout(Vec<u8>),
+    Stderr(Vec<u8>),
+}
+
+impl Line {
+    pub fn as_bytes(&self) -> &[u8] {
+        let (Line::Stdout(ln) | Line::Stderr(ln)) = self;
+        ln
+    }
+
+
+    pub fn is_stderr(&self) -> bool {
+        matches!(self, Line::Stderr(_))
+    }
+}
+
+
+/// Highlights text of line read from stderr in red. Red color
+/// is applied again after each SGR sequence that resets it
+pub fn highlight_stderr(ln: &[u8]) -> Vec<u8> {
+    const RED: &[u8] = b"\x1B[31m";
+    const DEFAULT_FG: &[u8] = b"\x1B[39m";
+
+    let mut highlighted = Vec::with_capacity(ln.len());
+    let mut rest = ln;
+
+    while let Some(csi) = rest.windows(2).position(|w| w == b"\x1B[") {
+        let params_start = csi + 2;
+        let params_len = rest[params_start..]
+            .iter()
+            .take_while(|b| b.is_ascii_digit() || **b == b';')
+            .count();
+        let params_end = params_start + params_len;
+
+        if rest.get(params_end) != Some(&b'm') {
+            highlighted.extend_from_slice(&rest[..params_start]);
+            rest = &rest[params_start..];
+
+            continue
+        }
+
+        highlighted.extend_from_slice(&rest[..=params_end]);
+
+        if resets_fg(&rest[params_start..params_end]) {
+            highlighted.extend_from_slice(RED);
+        }
+
+        rest = &rest[params_end + 1..];
+    }
+
+    highlighted.extend_from_slice(rest);
+
+    wrap_with_sgr(&highlighted, RED, DEFAULT_FG)
+}
+
+
+/// Returns whether foreground color is default after SGR sequence
+/// with provided parameters, since later ones override earlier
+fn resets_fg(params: &[u8]) -> bool {
+    let mut resets = false;
+    let mut params = params.split(|b| *b == b';');
+
+    while let Some(p) = params.next() {
+        match p {
+            // Parameters of 256 and RGB colors aren't resets
+            b"38" | b"48" => {
+                let color_params = match params.next() {
+                    Some(b"5") => 1,
+                    Some(b"2") => 3,
+                    _ => 0,
+                };
+                for _ in 0..color_params {
+                    params.next();
+                }
+
+                if p == b"38" {
+                    resets = false;
+                }
+            }
+
+            b"39" => resets = true,
+
+            [b'3' | b'9', b'0'..=b'7'] => resets = false,
+
+            _ if p.iter().all(|b| *b == b'0') => resets = true,
+
+            _ => {}
+        }
+    }
+
+    resets
+}
+
+
+/// Surrounds line text with SGR escape sequences
+/// keeping end of line character outside of them
+pub fn wrap_with_sgr(ln: &[u8], sgr_start: &[u8], sgr_end: &[u8]) -> Vec<u8> {
+    let (text, eol) = match ln.strip_suffix(b"\n") {
+        Some(text) => (text, &b"\n"[..]),
+        None => (ln, &b""[..]),
+    };
+
+    [sgr_start, text, sgr_end, eol].concat()
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn highlight_stderr_reapplies_red_after_resets() {
+        let cases: [(&[u8], &[u8]); 9] = [
+            (b"error\n", b"\x1B[31merror\x1B[39m\n"),
+            (b"no eol", b"\x1B[31mno eol\x1B[39m"),
+            (b"a\x1B[mb", b"\x1B[31ma\x1B[m\x1B[31mb\x1B[39m"),
+            (b"a\x1B[0mb", b"\x1B[31ma\x1B[0m\x1B[31mb\x1B[39m"),
+            (b"a\x1B[1;39mb", b"\x1B[31ma\x1B[1;39m\x1B[31mb\x1B[39m"),
+            (b"a\x1B[1mb", b"\x1B[31ma\x1B[1mb\x1B[39m"),
+            (b"a\x1B[0;32mb", b"\x1B[31ma\x1B[0;32mb\x1B[39m"),
+            (b"a\x1B[38;5;0mb", b"\x1B[31ma\x1B[38;5;0mb\x1B[39m"),
+            (b"a\x1B[48;2;0;0;0mb", b"\x1B[31ma\x1B[48;2;0;0;0mb\x1B[39m"),
+        ];
+
+        for (ln, expected) in cases {
+            assert_eq!(
+                String::from_utf8_lossy(&highlight_stderr(ln)),
+                String::from_utf8_lossy(expected),
+                "{:?}", String::from_utf8_lossy(ln)
+            );
+        }
+    }
+
+
+    #[test]
+    fn highlight_stderr_keeps_other_escape_sequences() {
+        let cases: [(&[u8], &[u8]); 3] = [
+            (b"\x1B[2Kline", b"\x1B[31m\x1B[2Kline\x1B[39m"),
+            (b"\x1B[", b"\x1B[31m\x1B[\x1B[39m"),
+            (b"\x1B]8;;0m\x07", b"\x1B[31m\x1B]8;;0m\x07\x1B[39m"),
+        ];
+
+        for (ln, expected) in cases {
+            assert_eq!(
+                String::from_utf8_lossy(&highlight_stderr(ln)),
+                String::from_utf8_lossy(expected),
+                "{:?}", String::from_utf8_lossy(ln)
+            );
+        }
+    }
+}
@@ -0,0 +1,210 @@
+/// A module that manages named pipes of instance buffers
+/// which are listened by background page process (see --fifo-path)
+use std::{
+    ffi::OsString,
+    io::{BufRead, BufReader, Read, Seek, SeekFrom, Write},
+    os::unix::io::AsRawFd,
+    path::{Path, PathBuf},
+};
+use nix::{
+    fcntl::{flock, FlockArg},
+    sys::stat::Mode,
+    unistd::mkfifo,
+};
+
+
+/// Returns path of named pipe associated with instance
+/// buffer of neovim which listens on provided address
+pub fn instance_fifo_path(nvim_addr: &str, instance: &str) -> PathBuf {
+    let escape = |s: &str| s
+        .bytes()
+        .map(|b| if b.is_ascii_alphanumeric() || b == b'-' || b == b'_' {
+            char::from(b).to_string()
+        } else {
+            format!("%{b:02X}")
+        })
+        .collect::<String>();
+
+    std::env::temp_dir()
+        .join("neovim-page")
+        .join(format!("fifo-{}-{}", escape(nvim_addr), escape(instance)))
+}
+
+
+/// Creates named pipe and spawns background page process
+/// that appends everything written into it to instance buffer.
+/// Does nothing if named pipe is already listened. Pid file is locked
+/// meanwhile, so concurrent page processes won't spawn duplicates
+pub fn ensure_listened(
+    fifo_path: &Path,
+    nvim_addr: &str,
+    instance: &str
+) -> std::io::Result<()> {
+    if let Some(fifo_dir) = fifo_path.parent() {
+        std::fs::create_dir_all(fifo_dir)?;
+    }
+
+    let mut pid_file = std::fs::OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .open(pid_path(fifo_path))?;
+
+    // Lock is released when pid file is closed
+    flock(pid_file.as_raw_fd(), FlockArg::LockExclusive)?;
+
+    let mut pid = String::new();
+    pid_file.read_to_string(&mut pid)?;
+
+    let listener_pid = pid
+        .trim()
+        .parse::<u32>()
+        .ok();
+    if fifo_path.exists() && listener_pid.map_or(false, is_process_alive) {
+        log::trace!(target: "fifo", "{fifo_path:?} is listened by {pid}");
+
+        return Ok(())
+    }
+
+    // Named pipe could be left by killed listener
+    if let Err(e) = std::fs::remove_file(fifo_path) {
+        log::trace!(target: "fifo", "Cannot remove {fifo_path:?}: {e}");
+    }
+
+    mkfifo(fifo_path, Mode::S_IRUSR | Mode::S_IWUSR)?;
+
+    let listener = std::process::Command::new(std::env::current_exe()?)
+        .stdin(std::process::Stdio::null())
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .arg("-a")
+        .arg(nvim_addr)
+        .arg("-I")
+        .arg(instance)
+        .arg("--fifo-listen")
+        .arg(fifo_path)
+        .spawn()?;
+
+    pid_file.set_len(0)?;
+    pid_file.seek(SeekFrom::Start(0))?;
+    pid_file.write_all(listener.id().to_string().as_bytes())?;
+
+    Ok(())
+}
+
+
+/// Removes named pipe and forgets pid of its listener, which is
+/// current process. Pid file is locked like in `ensure_listened` and
+/// it's truncated instead of removal, so pending page processes that
+/// wait for lock of this file won't miss lock of a new one
+pub fn remove(fifo_path: &Path) {
+    let pid_file = std::fs::OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open(pid_path(fifo_path));
+
+    let mut pid_file = match pid_file {
+        Ok(pid_file) => pid_file,
+        Err(e) => {
+            log::trace!(target: "fifo", "Cannot open pid file of {fifo_path:?}: {e}");
+
+            return
+        }
+    };
+
+    if let Err(e) = flock(pid_file.as_raw_fd(), FlockArg::LockExclusive) {
+        log::warn!(target: "fifo", "Cannot lock pid file of {fifo_path:?}: {e}");
+
+        return
+    }
+
+    let mut pid = String::new();
+    if let Err(e) = pid_file.read_to_string(&mut pid) {
+        log::warn!(target: "fifo", "Cannot read pid file of {fifo_path:?}: {e}");
+
+        return
+    }
+
+    // Named pipe could be listened already by another process
+    if pid.trim() != std::process::id().to_string() {
+        log::trace!(target: "fifo", "{fifo_path:?} isn't listened by current process");
+
+        return
+    }
+
+    if let Err(e) = std::fs::remove_file(fifo_path) {
+        log::trace!(target: "fifo", "Cannot remove {fifo_path:?}: {e}");
+    }
+
+    if let Err(e) = pid_file.set_len(0) {
+        log::warn!(target: "fifo", "Cannot truncate pid file of {fifo_path:?}: {e}");
+    }
+}
+
+
+/// Reads lines from named pipe on separate thread. Named pipe
+/// is reopened each time when all its writers are gone
+pub fn read_lines_on_thread(
+    fifo_path: PathBuf,
+    tx: tokio::sync::mpsc::Sender<Vec<u8>>
+) {
+    std::thread::spawn(move || loop {
+        let fifo = match std::fs::File::open(&fifo_path) {
+            Ok(fifo) => fifo,
+            Err(e) => {
+                log::error!(target: "fifo", "Cannot open {fifo_path:?}: {e}");
+
+                return
+            }
+        };
+
+        let mut input = BufReader::new(fifo);
+
+        loop {
+            let mut ln = Vec::with_capacity(2048);
+
+            match input.read_until(b'\n', &mut ln) {
+                Ok(0) => break,
+
+                Ok(_) => {
+                    if tx.blocking_send(ln).is_err() {
+                        return
+                    }
+                }
+
+                Err(e) => {
+                    log::warn!(target: "fifo", "Error reading line: {e}");
+
+                    break
+                }
+            }
+        }
+    });
+}
+
+
+fn pid_path(fifo_path: &Path) -> PathBuf {
+    let mut pid_path = OsString::from(fifo_path);
+    pid_path.push(".pid");
+
+    PathBuf::from(pid_path)
+}
+
+
+#[cfg(target_os = "linux")]
+fn is_process_alive(pid: u32) -> bool {
+    Path::new("/proc")
+        .join(pid.to_string())
+        .exists()
+}
+
+#[cfg(not(target_os = "linux"))]
+fn is_process_alive(pid: u32) -> bool {
+    std::process::Command::new("kill")
+        .arg("-0")
+        .arg(pid.to_string())
+        .stderr(std::process::Stdio::null())
+        .status()
+        .map_or(false, |status| status.success())
+}
@@ -0,0 +1,79 @@
+//! A module that highlights text printed by -O in-process
+//! with bundled syntax definitions instead of spawning `bat`
+use syntect::{
+    easy::HighlightLines,
+    highlighting::ThemeSet,
+    parsing::{SyntaxReference, SyntaxSet},
+    util::{as_24_bit_terminal_escaped, LinesWithEndings},
+};
+
+
+/// Neovim filetypes which names differ from
+/// names and extensions of bundled syntaxes
+const FILETYPE_SYNTAXES: &[(&str, &str)] = &[
+    ("sh", "bash"),
+    ("zsh", "bash"),
+    ("make", "Makefile"),
+    ("dosbatch", "bat"),
+    ("javascriptreact", "js"),
+    ("ruby", "rb"),
+    ("perl", "pl"),
+    ("haskell", "hs"),
+    ("erlang", "erl"),
+    ("ocaml", "ml"),
+    ("clojure", "clj"),
+    ("objc", "m"),
+    ("objcpp", "mm"),
+    ("tex", "LaTeX"),
+    ("plaintex", "TeX"),
+    ("gitcommit", "Git Commit"),
+    ("gitconfig", "Git Config"),
+    ("gitrebase", "Git Rebase Todo"),
+    ("sshconfig", "ssh_config"),
+];
+
+const THEME: &str = "base16-ocean.dark";
+
+
+/// Returns text highlighted by terminal escape sequences
+/// or None if there's no syntax for provided filetype
+pub fn highlight(text: &str, filetype: &str) -> Option<String> {
+    let syntax_set = SyntaxSet::load_defaults_newlines();
+    let syntax = find_syntax(&syntax_set, filetype)?;
+    log::trace!(target: "highlight", "{filetype}: {}", syntax.name);
+
+    let theme_set = ThemeSet::load_defaults();
+    let theme = theme_set.themes.get(THEME)?;
+
+    let mut highlighter = HighlightLines::new(syntax, theme);
+    let mut highlighted = String::with_capacity(text.len() * 2);
+
+    for ln in LinesWithEndings::from(text) {
+        let ranges = match highlighter.highlight_line(ln, &syntax_set) {
+            Ok(ranges) => ranges,
+
+            Err(e) => {
+                log::warn!(target: "highlight", "Cannot highlight line: {e}");
+
+                return None
+            }
+        };
+
+        highlighted += &as_24_bit_terminal_escaped(&ranges, false);
+    }
+    highlighted += "\x1B[0m";
+
+    Some(highlighted)
+}
+
+
+fn find_syntax<'a>(syntax_set: &'a SyntaxSet, filetype: &str) -> Option<&'a SyntaxReference> {
+    let token = FILETYPE_SYNTAXES
+        .iter()
+        .find(|(ft, _)| *ft == filetype)
+        .map_or(filetype, |(_, syntax)| syntax);
+
+    syntax_set
+        .find_syntax_by_name(token)
+        .or_else(|| syntax_set.find_syntax_by_token(token))
+}
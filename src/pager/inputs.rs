@@ -0,0 +1,236 @@
+/// A module that reads inputs provided with --fifo and additional
+/// --exec into their own output buffers concurrently with main input
+use crate::{
+    cli::NamedInput,
+    exec::{self, Line},
+    neovim::{Actions, OutputCommands},
+    NeovimBuffer,
+};
+use connection::NotificationFromNeovim;
+use std::io::Write;
+use tokio::sync::mpsc::Receiver;
+
+
+/// This struct holds readers of named inputs spawned
+/// as tokio tasks. Page must wait for them before exit
+/// to not close output buffers that are still read
+#[derive(Default)]
+pub struct NamedInputs {
+    readers: Vec<tokio::task::JoinHandle<()>>,
+}
+
+impl NamedInputs {
+    pub fn push(&mut self, reader: NamedInputReader) {
+        let handle = tokio::task::spawn(reader.read());
+
+        self.readers
+            .push(handle);
+    }
+
+
+    /// Blocks until each named input is read
+    /// or until its output buffer is closed
+    pub async fn wait(&mut self) {
+        for reader in self.readers.drain(..) {
+            if let Err(e) = reader.await {
+                log::error!(target: "named input", "Reader failed: {e}");
+            }
+        }
+    }
+}
+
+
+/// Reads named input into its output buffer and handles
+/// notifications routed to that buffer from neovim side
+pub struct NamedInputReader {
+    pub input: NamedInput,
+    pub buf: NeovimBuffer,
+    pub buf_opts: OutputCommands,
+    pub initial_buf_nr: i64,
+    pub sink: std::fs::File,
+    pub rx: Receiver<NotificationFromNeovim>,
+    pub nvim_actions: Actions,
+}
+
+impl NamedInputReader {
+    async fn read(mut self) {
+        log::trace!(target: "named input", "{:?}", self.input);
+
+        match self.input.clone() {
+            NamedInput::Fifo(fifo) => {
+                let path = fifo.path;
+
+                let (tx, mut lines) = tokio::sync::mpsc::channel(64);
+                // Named pipe blocks on open until its writer appears
+                let open = move || std::fs::File::open(&path)
+                    .map_err(|e| std::io::Error::new(e.kind(), format!("{path}: {e}")));
+                exec::read_lines_on_thread(open, Line::Stdout, tx);
+
+                if let Copied::Eof = self.copy_lines(&mut lines, false).await {
+                    self.nvim_actions
+                        .notify_end_of_input()
+                        .await;
+                }
+            }
+
+            NamedInput::Exec(cmd) => loop {
+                let mut execution = match exec::spawn(&cmd) {
+                    Ok(execution) => execution,
+                    Err(e) => {
+                        log::error!(target: "named input", "Cannot execute {cmd}: {e}");
+                        return
+                    }
+                };
+
+                let rerun_requested = match self.copy_lines(&mut execution.lines, true).await {
+                    Copied::Eof => false,
+
+                    Copied::Rerun => {
+                        execution.kill()
+                            .await;
+
+                        true
+                    }
+
+                    Copied::BufferClosed => return,
+                };
+
+                if !rerun_requested {
+                    match execution.wait().await {
+                        Ok(status) => {
+                            self.nvim_actions
+                                .notify_exit_status(&self.buf, exec::exit_code(status), execution.elapsed())
+                                .await;
+                        }
+                        Err(e) => {
+                            log::error!(target: "named input", "Executed command failed: {e}");
+                        }
+                    }
+
+                    if !self.wait_for_rerun().await {
+                        return
+                    }
+                }
+
+                if !self.recreate_buffer().await {
+                    return
+                }
+            },
+        }
+    }
+
+
+    /// Clears content of output buffer by recreating it, so nothing
+    /// is left in scrollback. Returns false if PTY device couldn't be opened
+    async fn recreate_buffer(&mut self) -> bool {
+        let outp = self.nvim_actions
+            .recreate_output_buffer(&self.buf, self.initial_buf_nr, self.buf_opts.clone())
+            .await;
+
+        match std::fs::OpenOptions::new()
+            .append(true)
+            .open(&outp.pty_path)
+        {
+            Ok(sink) => {
+                self.sink = sink;
+                self.buf = outp.buf;
+
+                true
+            }
+
+            Err(e) => {
+                log::error!(target: "named input", "Cannot open recreated PTY device: {e}");
+
+                false
+            }
+        }
+    }
+
+
+    /// Writes lines into output buffer until input ends, until output
+    /// buffer will be closed or until `:PageRerun` will be invoked
+    /// (if input is rerunnable, otherwise it's ignored)
+    async fn copy_lines(&mut self, lines: &mut Receiver<Line>, rerunnable: bool) -> Copied {
+        loop {
+            tokio::select! {
+                ln = lines.recv() => {
+                    let Some(ln) = ln else {
+                        return Copied::Eof
+                    };
+
+                    let written = match ln {
+                        Line::Stdout(ln) => self.sink.write_all(&ln),
+                        Line::Stderr(ln) => self.sink.write_all(&exec::highlight_stderr(&ln)),
+                    };
+
+                    if let Err(e) = written {
+                        log::info!(target: "named input", "Output buffer is closed: {e}");
+                        return Copied::BufferClosed
+                    }
+                }
+
+                notification = self.rx.recv() => match notification {
+                    Some(NotificationFromNeovim::Pause) => {
+                        if !self.wait_for_resume().await {
+                            return Copied::BufferClosed
+                        }
+                    }
+
+                    Some(NotificationFromNeovim::Rerun) if rerunnable => return Copied::Rerun,
+
+                    Some(NotificationFromNeovim::BufferClosed) | None => return Copied::BufferClosed,
+
+                    Some(n) => log::trace!(target: "named input", "Skipped: {n:?}"),
+                },
+            }
+        }
+    }
+
+
+    /// Blocks until `:PageResume` will be invoked on neovim side.
+    /// Returns false if output buffer was closed meanwhile
+    async fn wait_for_resume(&mut self) -> bool {
+        self.nvim_actions
+            .notify_paused(true)
+            .await;
+
+        loop {
+            match self.rx.recv().await {
+                Some(NotificationFromNeovim::Resume) => {
+                    self.nvim_actions
+                        .notify_paused(false)
+                        .await;
+
+                    return true
+                }
+
+                Some(NotificationFromNeovim::BufferClosed) | None => return false,
+
+                Some(n) => log::trace!(target: "named input", "Skipped: {n:?}"),
+            }
+        }
+    }
+
+
+    /// Blocks until `:PageRerun` will be invoked on neovim side.
+    /// Returns false if output buffer was closed meanwhile
+    async fn wait_for_rerun(&mut self) -> bool {
+        loop {
+            match self.rx.recv().await {
+                Some(NotificationFromNeovim::Rerun) => return true,
+
+                Some(NotificationFromNeovim::BufferClosed) | None => return false,
+
+                Some(n) => log::trace!(target: "named input", "Skipped: {n:?}"),
+            }
+        }
+    }
+}
+
+
+/// Reason why copying of lines into output buffer was stopped
+enum Copied {
+    Eof,
+    Rerun,
+    BufferClosed,
+}
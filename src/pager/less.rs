@@ -0,0 +1,355 @@
+//! A module that translates command line of `less` into options of page,
+//! so it could be used by programs that invoke $PAGER with less flags.
+//! It's active when page is invoked as `less` or with --less-compat
+//! (then only arguments after it are treated as flags of less)
+use std::ffi::OsString;
+
+
+/// Flags without value which have no meaning for page
+const IGNORED_FLAGS: &[char] = &[
+    'R', 'r', 'X', 'K', 'e', 'E', 's', 'M', 'm', 'Q', 'q', 'c', 'C',
+    'f', 'g', 'G', 'w', 'W', '~', 'J', 'a', 'B', 'd', 'L', 'u', 'U', 'n',
+];
+
+/// Flags followed by value which have no meaning for page
+const IGNORED_VALUE_FLAGS: &[char] = &[
+    'b', 'D', 'h', 'j', 'k', 'P', 'T', 'y', 'z', '#',
+];
+
+const IGNORED_LONG_FLAGS: &[&str] = &[
+    "RAW-CONTROL-CHARS", "raw-control-chars", "no-init", "quit-at-eof",
+    "QUIT-AT-EOF", "squeeze-blank-lines", "LONG-PROMPT", "long-prompt",
+    "quiet", "QUIET", "silent", "SILENT", "clear-screen", "CLEAR-SCREEN",
+    "force", "hilite-search", "HILITE-SEARCH", "hilite-unread",
+    "HILITE-UNREAD", "quit-on-intr", "no-keypad", "mouse", "MOUSE",
+    "use-color", "status-column", "tilde", "no-lessopen", "line-numbers",
+    "redraw-on-quit", "incsearch", "no-histdups", "no-number-headers",
+    "follow-name", "save-marks", "search-skip-screen", "UNDERLINE-SPECIAL",
+    "underline-special",
+];
+
+
+/// Returns whether page is invoked as `less` or with --less-compat
+pub fn is_enabled(args: &[OsString]) -> bool {
+    let invoked_as_less = args
+        .first()
+        .and_then(|arg0| std::path::Path::new(arg0).file_name())
+        .map_or(false, |name| name == "less");
+
+    invoked_as_less || args
+        .iter()
+        .any(|arg| arg == "--less-compat")
+}
+
+
+/// Translates recognized flags from $LESS and command line to options of
+/// page (e.g. -F to -O, +G to -f, +/pattern to search) and drops harmless
+/// ones. Other arguments are passed as is, so page's own options still work,
+/// but they should be provided before --less-compat since many of page's
+/// short options are also flags of less
+pub fn translate_args(args: Vec<OsString>) -> Vec<OsString> {
+    let less_env = std::env::var("LESS")
+        .ok();
+
+    translate_args_with_env(args, less_env.as_deref())
+}
+
+
+/// Translates arguments with provided value of $LESS
+fn translate_args_with_env(args: Vec<OsString>, less_env: Option<&str>) -> Vec<OsString> {
+    let less_args_start = args
+        .iter()
+        .take_while(|arg| *arg != "--")
+        .position(|arg| arg == "--less-compat")
+        .map_or(1, |i| i + 1);
+
+    let mut args = args.into_iter();
+    let mut translated = Translated::default();
+
+    translated.args
+        .extend(args.by_ref().take(less_args_start));
+
+    if let Some(less_env) = less_env {
+        match shell_words::split(less_env) {
+            Ok(env_args) => {
+                // $LESS could contain flags without leading dash like "FRX"
+                let env_args = env_args
+                    .into_iter()
+                    .map(|arg| match arg.chars().next() {
+                        Some('-' | '+') => arg,
+                        _ => format!("-{arg}"),
+                    })
+                    .map(OsString::from)
+                    .collect::<Vec<_>>();
+
+                translated.translate(&mut env_args.into_iter());
+            }
+
+            Err(e) => log::warn!(target: "less", "Cannot parse $LESS: {e}"),
+        }
+    }
+
+    translated.translate(&mut args);
+
+    translated.into_args()
+}
+
+
+#[derive(Default)]
+struct Translated {
+    args: Vec<OsString>,
+    flags: Vec<&'static str>,
+    commands: Vec<String>,
+    pattern: Option<String>,
+}
+
+impl Translated {
+    fn translate(&mut self, args: &mut impl Iterator<Item = OsString>) {
+        while let Some(arg) = args.next() {
+            let Some(s) = arg.to_str() else {
+                self.args.push(arg);
+                continue
+            };
+
+            if s == "--" {
+                self.args.push(arg);
+                self.args.extend(args.by_ref());
+                return
+            }
+
+            if let Some(long) = s.strip_prefix("--") {
+                self.translate_long(long, args);
+            } else if let Some(cmd) = s.strip_prefix('+') {
+                self.translate_initial_command(cmd);
+            } else if let Some(flags) = s.strip_prefix('-').filter(|f| !f.is_empty()) {
+                self.translate_short(flags, args);
+            } else {
+                self.args.push(arg);
+            }
+        }
+    }
+
+
+    /// Translates cluster of short flags like -FRX or -x4
+    fn translate_short(&mut self, flags: &str, args: &mut impl Iterator<Item = OsString>) {
+        for (i, flag) in flags.char_indices() {
+            match flag {
+                'F' => self.flags.push("-O"),
+                'S' => self.commands.push(String::from("setlocal nowrap")),
+                'N' => self.commands.push(String::from("setlocal number")),
+                'i' | 'I' => self.commands.push(String::from("setlocal ignorecase")),
+
+                _ if flag == 'x' || flag == 'p' || IGNORED_VALUE_FLAGS.contains(&flag) => {
+                    let value = match &flags[i + flag.len_utf8()..] {
+                        "" => args
+                            .next()
+                            .map(|v| v.to_string_lossy().to_string()),
+                        attached => Some(String::from(attached)),
+                    };
+
+                    match (flag, value) {
+                        ('x', Some(tabs)) => self.tabstop(&tabs),
+                        ('p', Some(pattern)) => self.pattern = Some(pattern),
+                        (_, value) => log::debug!(target: "less", "ignore -{flag} {value:?}"),
+                    }
+
+                    return
+                }
+
+                _ if IGNORED_FLAGS.contains(&flag) => {
+                    log::debug!(target: "less", "ignore -{flag}");
+                }
+
+                _ => {
+                    // Not a less flag, so page should handle it
+                    self.args.push(OsString::from(format!("-{}", &flags[i..])));
+                    return
+                }
+            }
+        }
+    }
+
+
+    fn translate_long(&mut self, long: &str, args: &mut impl Iterator<Item = OsString>) {
+        let (name, value) = match long.split_once('=') {
+            Some((name, value)) => (name, Some(value)),
+            None => (long, None),
+        };
+
+        match name {
+            "quit-if-one-screen" => self.flags.push("-O"),
+            "chop-long-lines" => self.commands.push(String::from("setlocal nowrap")),
+            "LINE-NUMBERS" => self.commands.push(String::from("setlocal number")),
+            "ignore-case" | "IGNORE-CASE" => self.commands.push(String::from("setlocal ignorecase")),
+
+            "tabs" | "pattern" => {
+                let value = value
+                    .map(String::from)
+                    .or_else(|| args
+                        .next()
+                        .map(|v| v.to_string_lossy().to_string()));
+
+                match (name, value) {
+                    ("tabs", Some(tabs)) => self.tabstop(&tabs),
+                    ("pattern", Some(pattern)) => self.pattern = Some(pattern),
+                    _ => {}
+                }
+            }
+
+            _ if IGNORED_LONG_FLAGS.contains(&name) => {
+                log::debug!(target: "less", "ignore --{long}");
+            }
+
+            _ => self.args.push(OsString::from(format!("--{long}"))),
+        }
+    }
+
+
+    /// Translates commands executed on start like +G or +/pattern
+    fn translate_initial_command(&mut self, cmd: &str) {
+        let cmd = cmd.strip_prefix('+').unwrap_or(cmd);
+
+        if cmd == "G" || cmd == "F" {
+            self.flags.push("-f");
+        } else if let Some(pattern) = cmd
+            .strip_prefix('/')
+            .or_else(|| cmd.strip_prefix('?'))
+        {
+            self.pattern = Some(String::from(pattern));
+        } else {
+            log::debug!(target: "less", "ignore +{cmd}");
+        }
+    }
+
+
+    /// Only the first of tab stops is used, like `-x4,8` is set as 4
+    fn tabstop(&mut self, tabs: &str) {
+        match tabs.split(',').next().map(str::parse::<u16>) {
+            Some(Ok(tabstop)) => self.commands.push(format!("setlocal tabstop={tabstop}")),
+            _ => log::debug!(target: "less", "ignore tabs {tabs}"),
+        }
+    }
+
+
+    /// Translated flags are appended like in config file, since
+    /// -O with optional value might consume positional argument.
+    /// Commands and search pattern are provided with hidden options
+    /// to not override -e and to be passed to neovim as is. Text isn't
+    /// read yet when output buffer is created, so pattern is only
+    /// set to search register to be found by 'n' keypress
+    fn into_args(mut self) -> Vec<OsString> {
+        let mut translated = self.flags
+            .into_iter()
+            .map(OsString::from)
+            .collect::<Vec<_>>();
+
+        if !self.commands.is_empty() {
+            translated.push(OsString::from("--less-commands"));
+            translated.push(OsString::from(self.commands.join(" | ")));
+        }
+        if let Some(pattern) = self.pattern {
+            translated.push(OsString::from("--less-pattern"));
+            translated.push(OsString::from(pattern));
+        }
+
+        log::trace!(target: "less", "translated args: {translated:?}");
+
+        let end = self.args
+            .iter()
+            .position(|arg| arg == "--")
+            .unwrap_or(self.args.len());
+        self.args.splice(end..end, translated);
+
+        self.args
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn translate(args: &[&str], less_env: Option<&str>) -> Vec<String> {
+        let args = args
+            .iter()
+            .map(OsString::from)
+            .collect();
+
+        translate_args_with_env(args, less_env)
+            .into_iter()
+            .map(|arg| arg.to_string_lossy().to_string())
+            .collect()
+    }
+
+
+    #[test]
+    fn translates_flags_after_less_compat() {
+        let cases: [(&[&str], &[&str]); 12] = [
+            (&["page", "--less-compat", "-FRX"], &["page", "--less-compat", "-O"]),
+            (&["page", "--less-compat", "--quit-if-one-screen", "--RAW-CONTROL-CHARS"], &["page", "--less-compat", "-O"]),
+            (&["page", "--less-compat", "-SN"], &["page", "--less-compat", "--less-commands", "setlocal nowrap | setlocal number"]),
+            (&["page", "--less-compat", "-x4,8"], &["page", "--less-compat", "--less-commands", "setlocal tabstop=4"]),
+            (&["page", "--less-compat", "-x", "2"], &["page", "--less-compat", "--less-commands", "setlocal tabstop=2"]),
+            (&["page", "--less-compat", "--tabs=3"], &["page", "--less-compat", "--less-commands", "setlocal tabstop=3"]),
+            (&["page", "--less-compat", "+G"], &["page", "--less-compat", "-f"]),
+            (&["page", "--less-compat", "++/err"], &["page", "--less-compat", "--less-pattern", "err"]),
+            (&["page", "--less-compat", "-p", "a b"], &["page", "--less-compat", "--less-pattern", "a b"]),
+            (&["page", "--less-compat", "-j", "5", "-Pprompt", "-#8"], &["page", "--less-compat"]),
+            (&["page", "--less-compat", "-Rt", "--unknown"], &["page", "--less-compat", "-t", "--unknown"]),
+            (&["page", "--less-compat", "-F", "--", "-S"], &["page", "--less-compat", "-O", "--", "-S"]),
+        ];
+
+        for (args, expected) in cases {
+            assert_eq!(translate(args, None), expected, "{args:?}");
+        }
+    }
+
+
+    #[test]
+    fn translates_flags_when_invoked_as_less() {
+        let cases: [(&[&str], &[&str]); 3] = [
+            (&["less", "-F", "file"], &["less", "file", "-O"]),
+            (&["/usr/bin/less", "+F", "file"], &["/usr/bin/less", "file", "-f"]),
+            (&["less", "-i", "--", "-file"], &["less", "--less-commands", "setlocal ignorecase", "--", "-file"]),
+        ];
+
+        for (args, expected) in cases {
+            assert_eq!(translate(args, None), expected, "{args:?}");
+        }
+    }
+
+
+    #[test]
+    fn translates_less_env_before_provided_flags() {
+        let cases: [(&str, &[&str], &[&str]); 4] = [
+            ("FRX", &["less", "file"], &["less", "file", "-O"]),
+            ("-R -S", &["less"], &["less", "--less-commands", "setlocal nowrap"]),
+            ("-x4", &["less", "-x8"], &["less", "--less-commands", "setlocal tabstop=4 | setlocal tabstop=8"]),
+            ("'-p", &["less"], &["less"]),
+        ];
+
+        for (less_env, args, expected) in cases {
+            assert_eq!(translate(args, Some(less_env)), expected, "{less_env:?} {args:?}");
+        }
+    }
+
+
+    #[test]
+    fn detects_less_invocation() {
+        let cases: [(&[&str], bool); 4] = [
+            (&["less", "file"], true),
+            (&["/usr/bin/less"], true),
+            (&["page", "--less-compat"], true),
+            (&["page", "-F"], false),
+        ];
+
+        for (args, expected) in cases {
+            let args = args
+                .iter()
+                .map(OsString::from)
+                .collect::<Vec<_>>();
+
+            assert_eq!(is_enabled(&args), expected, "{args:?}");
+        }
+    }
+}
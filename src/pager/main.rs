@@ -1,6 +1,18 @@
 pub(crate) mod cli;
 pub(crate) mod neovim;
 pub(crate) mod context;
+pub(crate) mod exec;
+pub(crate) mod inputs;
+pub(crate) mod fifo;
+pub(crate) mod config;
+pub(crate) mod man;
+pub(crate) mod diff;
+pub(crate) mod display_width;
+pub(crate) mod render;
+pub(crate) mod less;
+pub(crate) mod persist;
+#[cfg(feature = "highlight")]
+pub(crate) mod highlight;
 
 pub type NeovimConnection = connection::NeovimConnection<neovim::Actions>;
 pub type NeovimBuffer = connection::Buffer<connection::IoWrite>;
@@ -15,11 +27,74 @@ async fn main() {
 
     main::warn_if_incompatible_options(&env_ctx.opt);
 
+    if let Some(ref instance) = env_ctx.opt.fifo_path {
+        main::print_fifo_path_and_exit(&env_ctx.opt, instance);
+    }
+
+    if env_ctx.opt.ctl.is_some() {
+        main::exit_if_ctl_without_address(&env_ctx.opt);
+    }
+
+    if env_ctx.opt.output.man {
+        main::exit_if_man_not_piped(&env_ctx);
+    }
+
     validate_files(env_ctx).await;
 }
 
 mod main {
 
+    // Named pipe is listened by background page process,
+    // so current process only ensures that it's running
+    pub fn print_fifo_path_and_exit(opt: &super::cli::Options, instance: &str) -> ! {
+        let Some(ref nvim_addr) = opt.address else {
+            log::error!(
+                target: "usage",
+                "Named pipe (--fifo-path) requires address (-a or $NVIM) to be set"
+            );
+
+            std::process::exit(1)
+        };
+
+        let fifo_path = super::fifo::instance_fifo_path(nvim_addr, instance);
+
+        if let Err(e) = super::fifo::ensure_listened(&fifo_path, nvim_addr, instance) {
+            log::error!(target: "fifo", "Cannot listen named pipe {fifo_path:?}: {e}");
+
+            std::process::exit(1)
+        }
+
+        println!("{}", fifo_path.to_string_lossy());
+
+        std::process::exit(0)
+    }
+
+    // Instance buffers exist only in neovim to which page connects,
+    // so there's nothing to control in a newly spawned one
+    pub fn exit_if_ctl_without_address(opt: &super::cli::Options) {
+        if opt.address.is_none() {
+            log::error!(
+                target: "usage",
+                "Instance control (ctl) requires address (-a or $NVIM) to be set"
+            );
+
+            std::process::exit(1)
+        }
+    }
+
+    // Man page is read from stdin until EOF,
+    // which won't come soon when it's a terminal
+    pub fn exit_if_man_not_piped(env_ctx: &super::context::Env) {
+        if !env_ctx.input_from_pipe {
+            log::error!(
+                target: "usage",
+                "Man page (--man) should be piped into page, e.g. `man ls | page --man`"
+            );
+
+            std::process::exit(1)
+        }
+    }
+
     // Some options takes effect only when page would be
     // spawned from neovim's terminal
     pub fn warn_if_incompatible_options(opt: &super::cli::Options) {
@@ -34,6 +109,20 @@ mod main {
                 if address (-a or $NVIM) isn't set"
             );
         }
+        if opt.instances_list || opt.instances_close_all {
+            log::warn!(
+                target: "usage",
+                "Instances list and close (--list-instances --close-all-instances) \
+                are ignored if address (-a or $NVIM) isn't set"
+            );
+        }
+        if opt.instances_restore {
+            log::info!(
+                target: "usage",
+                "Instances (--restore-instances) are restored in a new neovim \
+                if address (-a or $NVIM) isn't set"
+            );
+        }
         if opt.is_output_split_implied() {
             log::warn!(
                 target: "usage",
@@ -48,6 +137,14 @@ mod main {
                 if address (-a or $NVIM) isn't set"
             );
         }
+        let split = &opt.output.split;
+        if split.tab || split.window.is_some() || split.window_reuse || split.window_target {
+            log::warn!(
+                target: "usage",
+                "Window target (--tab --window --window-reuse --window-target) is ignored \
+                if address (-a or $NVIM) isn't set"
+            );
+        }
     }
 }
 
@@ -138,57 +235,71 @@ async fn prefetch_lines(env_ctx: context::Env) {
     let mut prefetched_lines = Vec::with_capacity(i);
     let mut bytes = std::io::Read::bytes(prefetch_source);
 
-    'read_next_ln: while i > 0 {
-        let mut ln = Vec::with_capacity(*term_width);
+    // Lines are split into rows as they would be wrapped by terminal
+    let mut ln = Vec::with_capacity(*term_width);
+    let mut ln_width = display_width::LineWidth::new(*term_width);
 
-        for b in bytes.by_ref() {
-            match b {
-                Err(e) => {
-                    panic!("Failed to prefetch line from stdin: {e}")
-                }
-                Ok(eol @ b'\n') => {
-                    ln.push(eol);
-                    ln.shrink_to_fit();
-                    prefetched_lines.push(ln);
+    while i > 0 {
+        let Some(b) = bytes.next() else {
+            prefetched_lines.push(ln);
+
+            let nvim_render = |path: Option<&String>| env_ctx.opt.output.nvim_render
+                .then(|| render::NeovimRender {
+                    config: env_ctx.opt.config.clone(),
+                    path: path.cloned(),
+                });
+
+            if let PrefetchLinesUsage::Enabled {
+                source: PrefetchLinesSource::File(path),
+                ..
+            } = &env_ctx.prefetch_usage {
+
+                let extenstion = std::path::Path::new(&path)
+                    .extension()
+                    .map_or_else(
+                        || String::from(&env_ctx.opt.output.filetype),
+                        |s| s.to_string_lossy().to_string()
+                    );
+
+                dump_prefetched_lines_and_exit(
+                    prefetched_lines,
+                    &extenstion,
+                    nvim_render(Some(path)),
+                )
+            } else {
+
+                dump_prefetched_lines_and_exit(
+                    prefetched_lines,
+                    &env_ctx.opt.output.filetype,
+                    nvim_render(None),
+                )
+            };
+        };
+
+        match b {
+            Err(e) => {
+                panic!("Failed to prefetch line from stdin: {e}")
+            }
+            Ok(eol @ b'\n') => {
+                ln.push(eol);
+                ln.shrink_to_fit();
+                prefetched_lines.push(std::mem::take(&mut ln));
+                ln_width.reset();
+                i -= 1;
+            }
+            Ok(b) => {
+                if ln_width.push(b) {
+                    prefetched_lines.push(std::mem::take(&mut ln));
                     i -= 1;
-                    continue 'read_next_ln;
-                }
-                Ok(b) => {
-                    ln.push(b);
-                    if ln.len() == *term_width {
-                        prefetched_lines.push(ln);
-                        i -= 1;
-                        continue 'read_next_ln;
-                    }
                 }
+                ln.push(b);
             }
         }
+    }
 
+    // Beginning of the next row
+    if !ln.is_empty() {
         prefetched_lines.push(ln);
-
-        if let PrefetchLinesUsage::Enabled {
-            source: PrefetchLinesSource::File(path),
-            ..
-        } = env_ctx.prefetch_usage {
-
-            let extenstion = std::path::Path::new(&path)
-                .extension()
-                .map_or_else(
-                    || String::from(&env_ctx.opt.output.filetype),
-                    |s| s.to_string_lossy().to_string()
-                );
-
-            dump_prefetched_lines_and_exit(
-                prefetched_lines,
-                &extenstion
-            )
-        } else {
-
-            dump_prefetched_lines_and_exit(
-                prefetched_lines,
-                &env_ctx.opt.output.filetype,
-            )
-        };
     }
 
     let mut cli_ctx = context::check_usage::enter(env_ctx);
@@ -199,7 +310,11 @@ async fn prefetch_lines(env_ctx: context::Env) {
 }
 
 
-fn dump_prefetched_lines_and_exit(lines: Vec<Vec<u8>>, filetype: &str) -> ! {
+fn dump_prefetched_lines_and_exit(
+    lines: Vec<Vec<u8>>,
+    filetype: &str,
+    nvim_render: Option<render::NeovimRender>,
+) -> ! {
     log::info!(target: "dump", "{filetype}: {} lines", lines.len());
 
     let stdout;
@@ -208,6 +323,38 @@ fn dump_prefetched_lines_and_exit(lines: Vec<Vec<u8>>, filetype: &str) -> ! {
 
     let output: &mut dyn std::io::Write;
 
+    if let Some(nvim_render) = nvim_render {
+        if let Some(rendered) = nvim_render.render(&lines.concat(), filetype) {
+            log::info!(target: "dump", "use neovim");
+
+            let mut stdout = std::io::stdout().lock();
+            std::io::Write::write_all(&mut stdout, &rendered)
+                .expect("Cannot dump rendered lines");
+            std::io::Write::flush(&mut stdout)
+                .expect("Cannot flush");
+
+            std::process::exit(0)
+        }
+    }
+
+    #[cfg(feature = "highlight")]
+    if !filetype.is_empty() && filetype != "pager" {
+        let text = String::from_utf8_lossy(&lines.concat())
+            .to_string();
+
+        if let Some(highlighted) = highlight::highlight(&text, filetype) {
+            log::info!(target: "dump", "use syntect");
+
+            let mut stdout = std::io::stdout().lock();
+            std::io::Write::write_all(&mut stdout, highlighted.as_bytes())
+                .expect("Cannot dump highlighted lines");
+            std::io::Write::flush(&mut stdout)
+                .expect("Cannot flush");
+
+            std::process::exit(0)
+        }
+    }
+
     if !filetype.is_empty() && filetype != "pager" {
         let try_spawn_bat = std::process::Command::new("bat")
             .arg("--plain")
@@ -290,15 +437,51 @@ async fn manage_page_state(
 
     let mut api_actions = neovim_api_usage::begin(nvim_conn, &nvim_ctx);
 
+    if nvim_ctx.opt.ctl.is_some() {
+        let status = api_actions
+            .control_instance_buffer()
+            .await;
+
+        connection::close_and_exit_with_status(nvim_conn, status).await;
+    }
+
     api_actions
         .close_page_instance_buffer()
         .await;
+    api_actions
+        .close_all_instance_buffers()
+        .await;
+    api_actions
+        .print_instances()
+        .await;
+    api_actions
+        .restore_instance_buffers()
+        .await;
     api_actions
         .display_files()
         .await;
+    let mut named_inputs = api_actions
+        .open_named_inputs()
+        .await;
+
+    if nvim_ctx.opt.output.man {
+        api_actions
+            .display_man_page()
+            .await;
+    } else if nvim_ctx.opt.output.diff {
+        api_actions
+            .display_diff()
+            .await;
+    }
 
-    use context::connect_neovim::OutputBufferUsage;
-    if let OutputBufferUsage::Disabled = nvim_ctx.outp_buf_usage {
+    // Named inputs are waited as in `done` when output buffer is closed
+    if nvim_ctx.opt.output.man ||
+        nvim_ctx.opt.output.diff ||
+        nvim_ctx.outp_buf_usage.is_disabled()
+    {
+        named_inputs
+            .wait()
+            .await;
 
         connection::close_and_exit(nvim_conn).await;
     }
@@ -310,7 +493,13 @@ async fn manage_page_state(
             .find_instance_buffer(name)
             .await;
 
-        if let Some(active_inst_outp) = active_instance {
+        if let Some(mut active_inst_outp) = active_instance {
+
+            if nvim_ctx.inst_usage.is_enabled_and_should_replace_its_content() {
+                active_inst_outp = api_actions
+                    .recreate_instance_output_buffer(name, active_inst_outp)
+                    .await;
+            }
 
             let outp_ctx = context::output_buffer_available::enter(
                 nvim_ctx,
@@ -320,7 +509,8 @@ async fn manage_page_state(
             manage_output_buffer(
                 nvim_conn,
                 active_inst_outp.buf,
-                outp_ctx
+                outp_ctx,
+                named_inputs
             )
                 .await;
 
@@ -339,7 +529,8 @@ async fn manage_page_state(
             manage_output_buffer(
                 nvim_conn,
                 new_inst_outp.buf,
-                outp_ctx
+                outp_ctx,
+                named_inputs
             )
                 .await;
         }
@@ -357,7 +548,8 @@ async fn manage_page_state(
         manage_output_buffer(
             nvim_conn,
             new_outp.buf,
-            outp_ctx
+            outp_ctx,
+            named_inputs
         )
             .await;
     };
@@ -367,7 +559,8 @@ async fn manage_page_state(
 async fn manage_output_buffer(
     nvim_conn: &mut NeovimConnection,
     buf: NeovimBuffer,
-    outp_ctx: context::Output
+    outp_ctx: context::Output,
+    named_inputs: inputs::NamedInputs
 ) {
     log::info!(target: "context", "{outp_ctx:#?}");
 
@@ -376,6 +569,7 @@ async fn manage_output_buffer(
         &outp_ctx,
         buf,
         nvim_conn.channel,
+        named_inputs,
     );
 
     use context::connect_neovim::InstanceUsage;
@@ -384,6 +578,9 @@ async fn manage_output_buffer(
         outp_buf_actions
             .update_instance_buffer_title(name)
             .await;
+        outp_buf_actions
+            .register_instance_writer(name)
+            .await;
         outp_buf_actions
             .focus_on_instance_buffer(name)
             .await;
@@ -395,6 +592,10 @@ async fn manage_output_buffer(
             .await;
     }
 
+    outp_buf_actions
+        .update_buffer_source()
+        .await;
+
     outp_buf_actions
         .execute_commands()
         .await;
@@ -403,7 +604,15 @@ async fn manage_output_buffer(
         .await;
 
     if outp_ctx.input_from_pipe {
-        if outp_ctx.query_lines_count > 0 {
+        if let Some(ref fifo_path) = outp_ctx.opt.fifo_listen {
+            outp_buf_actions
+                .handle_fifo_output(fifo_path)
+                .await;
+        } else if let Some(cmd) = outp_ctx.opt.output.main_exec() {
+            outp_buf_actions
+                .handle_exec_output(cmd)
+                .await;
+        } else if outp_ctx.query_lines_count > 0 {
             outp_buf_actions
                 .handle_query_output()
                 .await;
@@ -422,6 +631,10 @@ async fn manage_output_buffer(
         .execute_disconnect_commands()
         .await;
 
+    outp_buf_actions
+        .persist_instance_buffer()
+        .await;
+
     outp_buf_actions
         .done()
         .await;
@@ -433,7 +646,14 @@ mod neovim_api_usage {
     use super::{
         NeovimConnection,
         context::Neovim,
-        neovim::{OutputBuffer, OutputCommands}
+        neovim::{OutputBuffer, OutputCommands},
+        cli::{NamedInput, Ctl, CtlAction},
+        inputs::{NamedInputs, NamedInputReader},
+        persist,
+        exec,
+        man,
+        diff,
+        display_width,
     };
 
     /// This struct implements actions that should be done
@@ -466,6 +686,229 @@ mod neovim_api_usage {
         }
 
 
+        /// Acts on instance buffer provided to `page ctl`. Returns exit
+        /// status which is 1 if instance isn't found or action is failed
+        pub async fn control_instance_buffer(&mut self) -> i32 {
+            let Some(Ctl::Ctl { instance, action }) = &self.nvim_ctx.opt.ctl else {
+                return 0
+            };
+
+            let nvim_actions = &mut self.nvim_conn.nvim_actions;
+
+            let Some(inst_outp) = nvim_actions
+                .find_instance_buffer(instance)
+                .await
+            else {
+                log::error!(target: "ctl", "Instance buffer not found: {instance}");
+
+                return 1
+            };
+
+            match action {
+                CtlAction::ScrollBottom => nvim_actions
+                    .scroll_instance_buffer(instance, true)
+                    .await,
+
+                CtlAction::ScrollTop => nvim_actions
+                    .scroll_instance_buffer(instance, false)
+                    .await,
+
+                CtlAction::Focus => nvim_actions
+                    .focus_instance_buffer(instance)
+                    .await,
+
+                CtlAction::Rename { title } => nvim_actions
+                    .update_buffer_title(&inst_outp.buf, title)
+                    .await,
+
+                CtlAction::SetFiletype { filetype } => nvim_actions
+                    .set_instance_filetype(instance, filetype)
+                    .await,
+
+                CtlAction::Clear => {
+                    // Buffer recreated here would be bound to this short-lived
+                    // process, so writer that's still connected clears it itself
+                    match nvim_actions.clear_instance_by_writer(instance).await {
+                        Ok(true) => return 0,
+                        Ok(false) => {}
+                        Err(e) => {
+                            log::error!(target: "ctl", "Cannot clear instance buffer {instance}: {e}");
+
+                            return 1
+                        }
+                    }
+
+                    let active_win = match nvim_actions.get_current_window().await {
+                        Ok(win) => win,
+                        Err(e) => {
+                            log::error!(target: "ctl", "Cannot clear instance buffer {instance}: {e}");
+
+                            return 1
+                        }
+                    };
+
+                    self.recreate_instance_output_buffer(instance, inst_outp)
+                        .await;
+
+                    if let Err(e) = self.nvim_conn.nvim_actions
+                        .switch_to_window(&active_win)
+                        .await
+                    {
+                        log::error!(target: "ctl", "Cannot switch back to active window: {e}");
+                    }
+                }
+
+                CtlAction::Save { path } => {
+                    // Path is relative to the shell, not to neovim
+                    let path = std::env::current_dir()
+                        .map(|cwd| cwd.join(path))
+                        .unwrap_or_else(|_| std::path::PathBuf::from(path));
+
+                    if let Err(e) = nvim_actions
+                        .save_instance_buffer(instance, &path.to_string_lossy())
+                        .await
+                    {
+                        log::error!(target: "ctl", "Cannot save instance buffer {instance}: {e}");
+
+                        return 1
+                    }
+                }
+            }
+
+            0
+        }
+
+
+        /// Closes all buffers marked as instance, when --close-all-instances is provided
+        pub async fn close_all_instance_buffers(&mut self) {
+            if self.nvim_ctx.opt.instances_close_all {
+                self.nvim_conn.nvim_actions
+                    .close_all_instance_buffers()
+                    .await;
+            }
+        }
+
+
+        /// Prints instance buffers as lines of tab separated values
+        /// or as JSON array, when --list-instances is provided
+        pub async fn print_instances(&mut self) {
+            let opt = &self.nvim_ctx.opt;
+
+            if !opt.instances_list {
+                return
+            }
+
+            let instances = self.nvim_conn.nvim_actions
+                .list_instances()
+                .await;
+
+            if opt.json {
+                let instances = instances
+                    .into_iter()
+                    .map(|inst| serde_json::json!({
+                        "name": inst.name,
+                        "buffer": inst.buf_nr,
+                        "pty_path": inst.pty_path,
+                        "line_count": inst.line_count,
+                        "writer_connected": inst.writer_connected,
+                    }))
+                    .collect();
+
+                println!("{}", serde_json::Value::Array(instances));
+
+                return
+            }
+
+            for inst in instances {
+                let writer = if inst.writer_connected { "connected" } else { "-" };
+
+                println!(
+                    "{}\t{}\t{}\t{}\t{writer}",
+                    inst.name, inst.buf_nr, inst.pty_path, inst.line_count
+                );
+            }
+        }
+
+
+        /// Recreates instance buffers persisted into state directory, when
+        /// --restore-instances is provided. Their content is written into
+        /// terminal, so further text appended by -I continues it
+        pub async fn restore_instance_buffers(&mut self) {
+            if !self.nvim_ctx.opt.instances_restore {
+                return
+            }
+
+            for inst in persist::read_instances() {
+                log::trace!(target: "restore instance", "{}: {} lines", inst.name, inst.lines.len());
+
+                let nvim_actions = &mut self.nvim_conn.nvim_actions;
+
+                if nvim_actions.find_instance_buffer(&inst.name).await.is_some() {
+                    log::info!(target: "restore instance", "{} is already opened", inst.name);
+
+                    continue
+                }
+
+                let split = &self.nvim_ctx.opt.output.split;
+                let target = connection::WindowTarget::new(
+                    split.tab,
+                    split.window,
+                    split.window_reuse,
+                    split.window_target
+                );
+
+                let outp = nvim_actions
+                    .create_switching_output_buffer(&target)
+                    .await;
+
+                self.prepare_oneoff_output_buffer()
+                    .await;
+
+                let nvim_actions = &mut self.nvim_conn.nvim_actions;
+
+                nvim_actions
+                    .mark_buffer_as_instance(
+                        &outp.buf,
+                        &inst.name,
+                        &outp.pty_path.to_string_lossy()
+                    )
+                    .await;
+
+                if !inst.filetype.is_empty() {
+                    nvim_actions
+                        .set_instance_filetype(&inst.name, &inst.filetype)
+                        .await;
+                }
+                if let Some(ref source_cmd) = inst.source_cmd {
+                    nvim_actions
+                        .mark_buffer_source(&outp.buf, source_cmd)
+                        .await;
+                }
+                if !inst.title.is_empty() {
+                    nvim_actions
+                        .update_buffer_title(&outp.buf, &inst.title)
+                        .await;
+                }
+
+                let write = std::fs::OpenOptions::new()
+                    .append(true)
+                    .open(&outp.pty_path)
+                    .and_then(|mut pty| inst.lines
+                        .iter()
+                        .try_for_each(|ln| std::io::Write::write_all(&mut pty, format!("{ln}\n").as_bytes())));
+
+                if let Err(e) = write {
+                    log::error!(target: "restore instance", "Cannot write {} content: {e}", inst.name);
+                }
+            }
+
+            let NeovimConnection { nvim_actions, initial_win_and_buf, .. } = self.nvim_conn;
+            nvim_actions
+                .switch_to_window_and_buffer(initial_win_and_buf)
+                .await;
+        }
+
+
         /// Opens each file provided as free arguments in separate buffers.
         /// Resets focus to initial buffer and window if further
         /// there will be created output buffer in split window,
@@ -494,11 +937,13 @@ mod neovim_api_usage {
                     .unwrap_or_default();
                 let writeable = nvim_ctx.opt.output.writable;
 
-                let file_buf_opts = OutputCommands::for_file_buffer(
+                let mut file_buf_opts = OutputCommands::for_file_buffer(
                     cmd_provided_by_user,
                     lua_provided_by_user,
                     writeable
                 );
+                file_buf_opts
+                    .add_less_translation(&nvim_ctx.opt.output);
 
                 nvim_actions
                     .prepare_output_buffer(*initial_buf_number, file_buf_opts)
@@ -524,71 +969,340 @@ mod neovim_api_usage {
         }
 
 
-        /// Returns buffer marked as instance,
-        /// together with path to PTY device
-        /// associated with it (if some exists)
-        pub async fn find_instance_buffer(
-            &mut self,
-            inst_name: &str
-        ) -> Option<OutputBuffer> {
-            let outp = self.nvim_conn.nvim_actions
-                .find_instance_buffer(inst_name)
-                .await;
-
-            outp
-        }
-
-
-        /// Creates a new output buffer
-        /// and then marks it as instance buffer
-        pub async fn create_instance_output_buffer(
-            &mut self,
-            inst_name: &str
-        ) -> OutputBuffer {
-            let outp = self
-                .create_oneoff_output_buffer()
-                .await;
-
-            self.nvim_conn.nvim_actions
-                .mark_buffer_as_instance(
-                    &outp.buf,
-                    inst_name,
-                    &outp.pty_path.to_string_lossy()
-                )
-                .await;
-
-            outp
-        }
-
-
-        /// Creates a new output buffer using split window if required.
-        /// Also sets some nvim options for better reading experience
-        pub async fn create_oneoff_output_buffer(&mut self) -> OutputBuffer {
+        /// Reads man page from page's stdin and opens it in plain buffer
+        /// that's handled by man plugin of neovim instead of output buffer
+        pub async fn display_man_page(&mut self) {
             let ApiActions {
                 nvim_conn: NeovimConnection {
                     nvim_actions,
                     initial_buf_number,
-                    channel,
-                    nvim_proc,
                     ..
                 },
                 nvim_ctx
             } = self;
 
-            let outp = if nvim_proc.is_some() && nvim_ctx.opt.files.is_empty() {
-                nvim_actions
-                    .create_replacing_output_buffer()
-                    .await
-            } else if nvim_ctx.outp_buf_usage.is_create_split() {
-                nvim_actions
+            let text = tokio::task::spawn_blocking(|| {
+                let mut text = vec![];
+                std::io::Read::read_to_end(&mut std::io::stdin(), &mut text)
+                    .map(|_| text)
+            }).await;
+
+            let text = match text {
+                Ok(Ok(text)) => text,
+
+                Ok(Err(e)) => {
+                    log::error!(target: "man", "Cannot read man page: {e}");
+
+                    return
+                }
+
+                Err(e) => panic!("Cannot read man page on thread: {e}"),
+            };
+
+            let lines = man::strip_formatting(&String::from_utf8_lossy(&text))
+                .lines()
+                .map(String::from)
+                .collect::<Vec<_>>();
+            let title = man::man_page_title(&lines);
+
+            if let Err(e) = nvim_actions.open_man_buffer(title, lines).await {
+                log::error!(target: "man", "Error opening man page: {e}");
+
+                return
+            }
+
+            let cmd_provided_by_user = &nvim_ctx.opt.output.command.as_deref()
+                .unwrap_or_default();
+            let lua_provided_by_user = &nvim_ctx.opt.output.lua.as_deref()
+                .unwrap_or_default();
+            let writeable = nvim_ctx.opt.output.writable;
+
+            let mut man_buf_opts = OutputCommands::for_man_buffer(
+                cmd_provided_by_user,
+                lua_provided_by_user,
+                writeable
+            );
+            man_buf_opts
+                .add_less_translation(&nvim_ctx.opt.output);
+
+            nvim_actions
+                .prepare_output_buffer(*initial_buf_number, man_buf_opts)
+                .await;
+        }
+
+
+        /// Reads diff from page's stdin into plain buffer which is handled
+        /// by `require('page').setup_diff`. Lines are appended by batches
+        /// as they're available since diff of `git log -p` might be huge
+        pub async fn display_diff(&mut self) {
+            let ApiActions {
+                nvim_conn: NeovimConnection {
+                    nvim_actions,
+                    initial_buf_number,
+                    ..
+                },
+                nvim_ctx
+            } = self;
+
+            let buf_nr = match nvim_actions.create_plain_buffer().await {
+                Ok(buf_nr) => buf_nr,
+
+                Err(e) => {
+                    log::error!(target: "diff", "Cannot create diff buffer: {e}");
+
+                    return
+                }
+            };
+
+            let diff_root = diff::repo_root();
+
+            let diff_buf_opts = OutputCommands::for_diff_buffer(
+                &diff_root.to_string_lossy(),
+                &nvim_ctx.opt.output
+            );
+            nvim_actions
+                .prepare_output_buffer(*initial_buf_number, diff_buf_opts)
+                .await;
+
+            let (tx, mut rx) = tokio::sync::mpsc::channel(2048);
+            exec::read_lines_on_thread(|| Ok(std::io::stdin()), exec::Line::Stdout, tx);
+
+            let mut first_batch = true;
+
+            while let Some(ln) = rx.recv().await {
+                let mut batch = vec![ln];
+                while let Ok(ln) = rx.try_recv() {
+                    batch.push(ln);
+                    if batch.len() == 2048 {
+                        break
+                    }
+                }
+
+                let lines = batch
+                    .iter()
+                    .map(|ln| {
+                        let ln = String::from_utf8_lossy(ln.as_bytes());
+                        display_width::strip_escapes(ln.trim_end_matches(['\n', '\r']))
+                    })
+                    .collect::<Vec<_>>();
+
+                let filetype = first_batch
+                    .then(|| diff::detect_filetype(&lines[0]));
+                first_batch = false;
+
+                if let Err(e) = nvim_actions
+                    .append_plain_buffer_lines(buf_nr, lines, filetype)
+                    .await
+                {
+                    log::error!(target: "diff", "Cannot append lines, buffer might be closed: {e}");
+
+                    return
+                }
+            }
+        }
+
+
+        /// Opens each input provided with --fifo and additional
+        /// --exec in its own output buffer in split below and then
+        /// spawns readers of these inputs. Focus is returned back
+        /// to previously active window afterwards
+        pub async fn open_named_inputs(&mut self) -> NamedInputs {
+            let mut named_inputs = NamedInputs::default();
+
+            let inputs = self.nvim_ctx.opt.output
+                .named_inputs();
+            if inputs.is_empty() {
+                return named_inputs
+            }
+
+            let active_win = self.nvim_conn.nvim_actions
+                .get_current_window()
+                .await
+                .expect("Cannot get currently active window to open named inputs");
+
+            for (input_nr, input) in inputs.into_iter().enumerate() {
+                let route_id = format!("{}:{input_nr}", self.nvim_ctx.page_id);
+                let rx = self.nvim_conn
+                    .open_route(route_id.clone());
+
+                let ApiActions {
+                    nvim_conn: NeovimConnection { nvim_actions, initial_buf_number, channel, .. },
+                    nvim_ctx
+                } = self;
+
+                let split_opts = crate::cli::SplitOptions::below();
+                let outp = nvim_actions
+                    .create_split_output_buffer(&split_opts)
+                    .await;
+
+                let outp_buf_opts = OutputCommands::for_output_buffer(
+                    &route_id,
+                    u128::from(*channel),
+                    0,
+                    matches!(input, NamedInput::Exec(_)),
+                    &nvim_ctx.opt.output
+                );
+                nvim_actions
+                    .prepare_output_buffer(*initial_buf_number, outp_buf_opts.clone())
+                    .await;
+
+                nvim_actions
+                    .update_buffer_title(&outp.buf, input.name())
+                    .await;
+                if let NamedInput::Exec(ref cmd) = input {
+                    nvim_actions
+                        .mark_buffer_source(&outp.buf, cmd)
+                        .await;
+                }
+
+                let sink = std::fs::OpenOptions::new()
+                    .append(true)
+                    .open(&outp.pty_path)
+                    .expect("Cannot open named input PTY device");
+
+                named_inputs.push(NamedInputReader {
+                    input,
+                    buf: outp.buf,
+                    buf_opts: outp_buf_opts,
+                    initial_buf_nr: *initial_buf_number,
+                    sink,
+                    rx,
+                    nvim_actions: nvim_actions.clone(),
+                });
+            }
+
+            if let Err(e) = self.nvim_conn.nvim_actions
+                .switch_to_window(&active_win)
+                .await
+            {
+                log::error!(target: "named input", "Cannot switch back to active window: {e}");
+            }
+
+            named_inputs
+        }
+
+
+        /// Returns buffer marked as instance,
+        /// together with path to PTY device
+        /// associated with it (if some exists)
+        pub async fn find_instance_buffer(
+            &mut self,
+            inst_name: &str
+        ) -> Option<OutputBuffer> {
+            let outp = self.nvim_conn.nvim_actions
+                .find_instance_buffer(inst_name)
+                .await;
+
+            outp
+        }
+
+
+        /// Creates a new output buffer
+        /// and then marks it as instance buffer
+        pub async fn create_instance_output_buffer(
+            &mut self,
+            inst_name: &str
+        ) -> OutputBuffer {
+            let outp = self
+                .create_oneoff_output_buffer()
+                .await;
+
+            self.nvim_conn.nvim_actions
+                .mark_buffer_as_instance(
+                    &outp.buf,
+                    inst_name,
+                    &outp.pty_path.to_string_lossy()
+                )
+                .await;
+
+            outp
+        }
+
+
+        /// Creates a new output buffer in place of instance buffer
+        /// which is deleted then, so replaced content starts
+        /// from empty terminal with nothing left in scrollback
+        pub async fn recreate_instance_output_buffer(
+            &mut self,
+            inst_name: &str,
+            inst_outp: OutputBuffer
+        ) -> OutputBuffer {
+            let nvim_actions = &mut self.nvim_conn.nvim_actions;
+
+            let outp = nvim_actions
+                .create_instance_replacing_output_buffer(&inst_outp.buf)
+                .await;
+            nvim_actions
+                .replace_instance_buffer(&inst_outp.buf, &outp.buf)
+                .await;
+            nvim_actions
+                .mark_buffer_as_instance(
+                    &outp.buf,
+                    inst_name,
+                    &outp.pty_path.to_string_lossy()
+                )
+                .await;
+
+            self.prepare_oneoff_output_buffer()
+                .await;
+
+            outp
+        }
+
+
+        /// Creates a new output buffer using split window if required.
+        /// Also sets some nvim options for better reading experience
+        pub async fn create_oneoff_output_buffer(&mut self) -> OutputBuffer {
+            let ApiActions {
+                nvim_conn: NeovimConnection {
+                    nvim_actions,
+                    nvim_proc,
+                    ..
+                },
+                nvim_ctx
+            } = self;
+
+            let outp = if nvim_proc.is_some() && nvim_ctx.opt.files.is_empty() {
+                nvim_actions
+                    .create_replacing_output_buffer()
+                    .await
+            } else if nvim_ctx.outp_buf_usage.is_create_split() {
+                nvim_actions
                     .create_split_output_buffer(&nvim_ctx.opt.output.split)
                     .await
             } else {
+                let split = &nvim_ctx.opt.output.split;
+                let target = connection::WindowTarget::new(
+                    split.tab,
+                    split.window,
+                    split.window_reuse,
+                    split.window_target
+                );
+
                 nvim_actions
-                    .create_switching_output_buffer()
+                    .create_switching_output_buffer(&target)
                     .await
             };
 
+            self.prepare_oneoff_output_buffer()
+                .await;
+
+            outp
+        }
+
+
+        /// Sets options, keymaps and commands on just created output buffer
+        async fn prepare_oneoff_output_buffer(&mut self) {
+            let ApiActions {
+                nvim_conn: NeovimConnection {
+                    nvim_actions,
+                    initial_buf_number,
+                    channel,
+                    ..
+                },
+                nvim_ctx
+            } = self;
+
             let channel = if let Some(chan_id) = &nvim_ctx.opt.pagerize_hidden {
                 chan_id[0]
             } else {
@@ -596,23 +1310,30 @@ mod neovim_api_usage {
             };
 
             let outp_buf_opts = OutputCommands::for_output_buffer(
-                nvim_ctx.page_id,
+                &nvim_ctx.page_id.to_string(),
                 channel,
                 nvim_ctx.query_lines_count,
+                nvim_ctx.opt.output.main_exec().is_some(),
                 &nvim_ctx.opt.output
             );
             nvim_actions
                 .prepare_output_buffer(*initial_buf_number, outp_buf_opts)
                 .await;
-
-            outp
         }
     }
 }
 
 mod output_buffer_usage {
-    use super::{NeovimConnection, NeovimBuffer, context::Output};
-    use connection::NotificationFromNeovim;
+    use super::{
+        NeovimConnection,
+        NeovimBuffer,
+        context::Output,
+        neovim::{OutputBuffer, OutputCommands},
+        exec::{self, Line},
+        inputs::NamedInputs,
+        fifo,
+    };
+    use connection::{NotificationFromNeovim, Value};
     use std::io::{Read, Write};
 
     /// This struct implements actions that should be done
@@ -621,9 +1342,16 @@ mod output_buffer_usage {
         nvim_conn: &'a mut NeovimConnection,
         outp_ctx: &'a Output,
         buf: NeovimBuffer,
+        buf_pty_path: std::path::PathBuf,
         sink: Option<Box<dyn std::io::Write>>,
+        stderr_buf: Option<NeovimBuffer>,
+        stderr_sink: Option<Box<dyn std::io::Write>>,
+        stderr_rx: Option<tokio::sync::mpsc::Receiver<NotificationFromNeovim>>,
+        exit_status: i32,
         pagerize_lines_displayed: usize,
         channel: u64,
+        progress: Progress,
+        named_inputs: NamedInputs,
     }
 
     pub fn begin<'a>(
@@ -631,19 +1359,28 @@ mod output_buffer_usage {
         outp_ctx: &'a Output,
         buf: NeovimBuffer,
         channel: u64,
+        named_inputs: NamedInputs,
     ) -> BufferActions<'a> {
         BufferActions {
             nvim_conn,
             outp_ctx,
             buf,
+            buf_pty_path: outp_ctx.buf_pty_path.clone(),
             sink: None,
+            stderr_buf: None,
+            stderr_sink: None,
+            stderr_rx: None,
+            exit_status: 0,
             pagerize_lines_displayed: 0,
             channel,
+            progress: Progress::new(outp_ctx.source_pid),
+            named_inputs,
         }
     }
 
     impl<'a> BufferActions<'a> {
-        /// This function updates buffer title depending on -n value.
+        /// This function updates buffer title depending on -n value
+        /// or on command line of process that writes into page's stdin.
         /// Icon symbol is received from neovim side
         /// and is prepended to the left of buffer title
         pub async fn update_buffer_title(&mut self) {
@@ -663,7 +1400,10 @@ mod output_buffer_usage {
                 .get_var_or(page_icon_key, page_icon_default)
                 .await;
 
-            if let Some(ref buf_name) = outp_ctx.opt.name {
+            let buf_name = outp_ctx.opt.name
+                .as_ref()
+                .or(outp_ctx.source_cmd.as_ref());
+            if let Some(buf_name) = buf_name {
                 buf_title.insert_str(0, buf_name);
             }
 
@@ -673,6 +1413,35 @@ mod output_buffer_usage {
         }
 
 
+        /// Stores command line of process that writes
+        /// into page's stdin in `b:page_source_cmd` variable
+        pub async fn update_buffer_source(&mut self) {
+            let BufferActions {
+                outp_ctx,
+                buf,
+                nvim_conn: NeovimConnection { nvim_actions, .. },
+                ..
+            } = self;
+
+            if let Some(ref source_cmd) = outp_ctx.source_cmd {
+                nvim_actions
+                    .mark_buffer_source(buf, source_cmd)
+                    .await;
+            }
+        }
+
+
+        /// Registers current page process as writer of instance buffer
+        pub async fn register_instance_writer(&mut self, inst_name: &str) {
+            let channel = self.nvim_conn.channel;
+            let page_id = self.outp_ctx.page_id.to_string();
+
+            self.nvim_conn.nvim_actions
+                .register_instance_writer(inst_name, channel, &page_id)
+                .await;
+        }
+
+
         /// This function updates instance buffer title
         /// depending on its name and -n value.
         /// Instance name will be prepended to the left
@@ -703,9 +1472,8 @@ mod output_buffer_usage {
         }
 
 
-        /// Resets instance buffer focus and content.
-        /// This is required to provide some functionality
-        /// not available through neovim API
+        /// Resets instance buffer focus (its content is replaced
+        /// by recreating instance buffer before it's available)
         pub async fn focus_on_instance_buffer(&mut self, inst_name: &str) {
             let BufferActions {
                 outp_ctx,
@@ -720,15 +1488,53 @@ mod output_buffer_usage {
             nvim_actions
                 .focus_instance_buffer(inst_name)
                 .await;
+        }
+
+
+        /// Clears content of output buffer (and of stderr buffer if
+        /// it's opened) by recreating them, so nothing is left in scrollback
+        async fn clear_content(&mut self) {
+            let BufferActions {
+                outp_ctx,
+                nvim_conn: NeovimConnection { nvim_actions, initial_buf_number, .. },
+                channel,
+                ..
+            } = self;
+
+            let outp_buf_opts = OutputCommands::for_output_buffer(
+                &outp_ctx.page_id.to_string(),
+                u128::from(*channel),
+                outp_ctx.query_lines_count,
+                true,
+                &outp_ctx.opt.output
+            );
+            let outp = nvim_actions
+                .recreate_output_buffer(&self.buf, *initial_buf_number, outp_buf_opts)
+                .await;
 
-            if outp_ctx.inst_usage.is_enabled_and_should_replace_its_content() {
+            self.close_sink();
+            self.buf = outp.buf;
+            self.buf_pty_path = outp.pty_path;
 
-                const CLEAR_SCREEN_SEQ: &[u8] = b"\x1B[3J\x1B[H\x1b[2J";
-                self
-                    .get_sink()
-                    .write_all(CLEAR_SCREEN_SEQ)
-                    .expect("Cannot write clear screen sequence");
+            use crate::context::connect_neovim::InstanceUsage;
+            if let InstanceUsage::Enabled { name, .. } = &self.outp_ctx.inst_usage {
+                self.nvim_conn.nvim_actions
+                    .mark_buffer_as_instance(&self.buf, name, &self.buf_pty_path.to_string_lossy())
+                    .await;
+                self.register_instance_writer(name)
+                    .await;
             }
+
+            let (Some(stderr_buf), Some(_)) = (self.stderr_buf.take(), self.stderr_sink.take()) else {
+                return
+            };
+
+            let stderr_buf_opts = self.stderr_buffer_commands();
+            let stderr_outp = self.nvim_conn.nvim_actions
+                .recreate_output_buffer(&stderr_buf, self.nvim_conn.initial_buf_number, stderr_buf_opts)
+                .await;
+
+            self.open_stderr_sink(stderr_outp);
         }
 
 
@@ -816,6 +1622,9 @@ mod output_buffer_usage {
                     .await
                     .expect("Cannot write next prefetched line");
 
+                self.line_has_been_read(ln)
+                    .await;
+
                 if self.outp_ctx
                     .should_pagerize(self.pagerize_lines_displayed)
                 {
@@ -823,28 +1632,27 @@ mod output_buffer_usage {
                 }
             }
 
-            // Then copy the rest of lines from stdin into buffer pty
-            let mut ln = Vec::with_capacity(2048);
-            for b in std::io::stdin().bytes() {
-
-                match b {
-                    Err(e) => {
-                        log::warn!(
-                            target: "output",
-                            "Error reading line from stdin: {e}"
-                        );
+            // Then copy the rest of lines from stdin into buffer pty.
+            // Stdin is read on separate thread, so pause could be
+            // requested even when producer is stalled in the middle of line
+            let (tx, mut lines) = tokio::sync::mpsc::channel(64);
+            exec::read_lines_on_thread(|| Ok(std::io::stdin()), Line::Stdout, tx);
 
-                        break;
-                    }
+            let mut publishing = Progress::publishing_interval();
 
-                    Ok(eol @ b'\n') => {
-                        ln.push(eol);
+            loop {
+                tokio::select! {
+                    ln = lines.recv() => {
+                        let Some(ln) = ln else {
+                            break
+                        };
 
-                        self.display_line(&ln)
+                        self.display_line(ln.as_bytes())
                             .await
                             .expect("Cannot write next line");
 
-                        ln.clear();
+                        self.line_has_been_read(ln.as_bytes())
+                            .await;
 
                         if self.outp_ctx
                             .should_pagerize(self.pagerize_lines_displayed)
@@ -853,21 +1661,59 @@ mod output_buffer_usage {
                         }
                     }
 
-                    Ok(b) => ln.push(b)
-                }
-            }
+                    notification = self.nvim_conn.rx.recv() => match notification {
+                        Some(NotificationFromNeovim::Pause) => {
+                            self.wait_for_resume()
+                                .await;
+                        }
 
-            log::trace!(target: "output", "got EOF");
+                        Some(NotificationFromNeovim::Clear) => {
+                            log::info!(target: "output-state", "Clear");
 
-            self.close_sink();
+                            self.clear_content()
+                                .await;
+                        }
 
-            self.display_line(&[b'\0'])
-                .await
-                .expect("Cannot write EOF sequence");
-        }
+                        Some(NotificationFromNeovim::BufferClosed) => {
+                            log::info!(target: "output-state", "Buffer closed");
 
+                            self.done()
+                                .await;
+                        }
+                        None => {
+                            log::info!(target: "output-state", "Neovim closed");
 
-        /// In case if -q <count> argument provided it
+                            self.done()
+                                .await;
+                        }
+
+                        Some(n) => {
+                            log::trace!(target: "output-state", "Skip notification: {n:?}");
+                        }
+                    },
+
+                    _ = publishing.tick() => {
+                        self.publish_progress_if_needed()
+                            .await;
+                    }
+                }
+            }
+
+            log::trace!(target: "output", "got EOF");
+
+            self.close_sink();
+
+            self.display_line(&[b'\0'])
+                .await
+                .expect("Cannot write EOF sequence");
+
+            self.progress.eof = true;
+            self.publish_progress()
+                .await;
+        }
+
+
+        /// In case if -q <count> argument provided it
         /// might block until next line will be request from neovim side.
         pub async fn handle_query_output(&mut self) {
             log::trace!(target: "output", "handle query output");
@@ -891,6 +1737,9 @@ mod output_buffer_usage {
                     .await
                     .expect("Cannot write next prefetched queried line");
 
+                self.line_has_been_read(ln)
+                    .await;
+
                 state.line_has_been_sent();
 
                 if self.outp_ctx
@@ -927,6 +1776,9 @@ mod output_buffer_usage {
                             .await
                             .expect("Cannot write next line");
 
+                        self.line_has_been_read(&ln)
+                            .await;
+
                         state.line_has_been_sent();
                         self.exchange_query_messages(&mut state)
                             .await;
@@ -949,6 +1801,10 @@ mod output_buffer_usage {
 
             self.close_sink();
 
+            self.progress.eof = true;
+            self.publish_progress()
+                .await;
+
             self.nvim_conn.nvim_actions
                 .notify_query_finished(state.how_many_lines_was_sent())
                 .await;
@@ -959,6 +1815,433 @@ mod output_buffer_usage {
         }
 
 
+        /// Runs command provided by --exec and writes its output
+        /// into PTY device associated with output buffer. After command
+        /// is finished waits until `:PageRerun` will be invoked
+        /// on neovim side or until --watch interval elapses
+        /// to clear output buffer and run it again. Command that's
+        /// still running is killed when `:PageRerun` is invoked
+        pub async fn handle_exec_output(&mut self, cmd: &str) {
+            log::trace!(target: "output", "handle exec output");
+
+            let watch_interval = self.outp_ctx.opt.output.watch
+                .map(std::time::Duration::from_secs_f64);
+
+            let mut differences = None;
+            if self.outp_ctx.opt.output.differences {
+                differences = Some(Differences::default());
+            }
+
+            if self.outp_ctx.opt.output.stderr_split {
+                self.create_stderr_buffer()
+                    .await;
+            }
+
+            loop {
+                let mut execution = match crate::exec::spawn(cmd) {
+                    Ok(execution) => execution,
+                    Err(e) => {
+                        log::error!(target: "output", "Cannot execute '{cmd}': {e}");
+
+                        self.done()
+                            .await;
+
+                        return
+                    }
+                };
+
+                self.progress = Progress::new(execution.pid());
+                self.publish_progress()
+                    .await;
+
+                let mut publishing = Progress::publishing_interval();
+
+                let rerun_requested = loop {
+                    tokio::select! {
+                        ln = execution.lines.recv() => {
+                            let Some(ln) = ln else {
+                                break false
+                            };
+
+                            self.display_executed_line(ln, differences.as_mut())
+                                .await;
+                        }
+
+                        notification = self.recv_notification() => match notification {
+                            Some(NotificationFromNeovim::Pause) => {
+                                self.wait_for_resume()
+                                    .await;
+                            }
+
+                            Some(NotificationFromNeovim::Rerun) => {
+                                log::info!(target: "output-state", "Rerun while command is running");
+
+                                execution.kill()
+                                    .await;
+
+                                break true
+                            }
+
+                            Some(NotificationFromNeovim::Clear) => {
+                                log::info!(target: "output-state", "Clear");
+
+                                self.clear_content()
+                                    .await;
+                            }
+
+                            Some(NotificationFromNeovim::BufferClosed) | None => {
+                                log::info!(target: "output-state", "Buffer closed");
+
+                                self.done()
+                                    .await;
+                            }
+
+                            Some(n) => {
+                                log::trace!(target: "output-state", "Skip notification: {n:?}");
+                            }
+                        },
+
+                        _ = publishing.tick() => {
+                            self.publish_progress_if_needed()
+                                .await;
+                        }
+                    }
+                };
+
+                if !rerun_requested {
+                    self.command_finished(&mut execution, watch_interval)
+                        .await;
+                }
+
+                if let Some(differences) = differences.as_mut() {
+                    differences.next_run();
+                }
+
+                self.clear_content()
+                    .await;
+            }
+        }
+
+
+        /// Displays line of --exec command in output buffer or in stderr
+        /// buffer and highlights it if it's changed or came from stderr
+        async fn display_executed_line(&mut self, ln: Line, differences: Option<&mut Differences>) {
+            self.line_has_been_read(ln.as_bytes())
+                .await;
+
+            if ln.is_stderr() && self.display_stderr_line(ln.as_bytes()) {
+                return
+            }
+
+            let mut displayed_ln = match differences {
+                Some(differences) => differences.highlight_changed(ln.as_bytes()),
+                None => std::borrow::Cow::from(ln.as_bytes()),
+            };
+
+            if let Line::Stderr(_) = ln {
+                displayed_ln = std::borrow::Cow::from(
+                    exec::highlight_stderr(&displayed_ln)
+                );
+            }
+
+            self.display_line(&displayed_ln)
+                .await
+                .expect("Cannot write next executed line");
+        }
+
+
+        /// Notifies neovim side about exit status of finished --exec command
+        /// and waits until it should be run again. Exits with its status
+        /// right away if --exit-status is provided without --watch
+        async fn command_finished(
+            &mut self,
+            execution: &mut exec::Execution,
+            watch_interval: Option<std::time::Duration>
+        ) {
+            let exit_status = match execution.wait().await {
+                Ok(status) => Some(crate::exec::exit_code(status)),
+                Err(e) => {
+                    log::error!(target: "output", "Executed command failed: {e}");
+                    None
+                }
+            };
+
+            log::trace!(target: "output", "command finished: {exit_status:?}");
+
+            self.progress.eof = true;
+            self.publish_progress()
+                .await;
+
+            if let Some(status) = exit_status {
+                self.exit_status = status;
+
+                self.nvim_conn.nvim_actions
+                    .notify_exit_status(&self.buf, status, execution.elapsed())
+                    .await;
+            } else {
+                self.nvim_conn.nvim_actions
+                    .notify_end_of_input()
+                    .await;
+            }
+
+            if self.outp_ctx.opt.output.exit_status && watch_interval.is_none() {
+                log::info!(target: "output-state", "Exit with status of command");
+
+                self.done()
+                    .await;
+            }
+
+            self.wait_for_rerun(watch_interval)
+                .await;
+        }
+
+
+        /// Appends lines written into named pipe to instance buffer
+        /// until it will be closed. Named pipe is reopened each time
+        /// when its writer is gone, so it could be written repeatedly
+        pub async fn handle_fifo_output(&mut self, fifo_path: &str) {
+            let (tx, mut lines) = tokio::sync::mpsc::channel(64);
+            fifo::read_lines_on_thread(fifo_path.into(), tx);
+
+            let mut publishing = Progress::publishing_interval();
+
+            loop {
+                tokio::select! {
+                    ln = lines.recv() => {
+                        let Some(ln) = ln else {
+                            break
+                        };
+
+                        self.display_line(&ln)
+                            .await
+                            .expect("Cannot write next line from named pipe");
+
+                        self.line_has_been_read(&ln)
+                            .await;
+                    }
+
+                    notification = self.nvim_conn.rx.recv() => match notification {
+                        Some(NotificationFromNeovim::Pause) => {
+                            self.wait_for_resume()
+                                .await;
+                        }
+
+                        Some(NotificationFromNeovim::Clear) => {
+                            log::info!(target: "output-state", "Clear");
+
+                            self.clear_content()
+                                .await;
+                        }
+
+                        Some(NotificationFromNeovim::BufferClosed) | None => {
+                            log::info!(target: "output-state", "Buffer closed");
+
+                            break
+                        }
+
+                        Some(n) => {
+                            log::trace!(target: "output-state", "Skip notification: {n:?}");
+                        }
+                    },
+
+                    _ = publishing.tick() => {
+                        self.publish_progress_if_needed()
+                            .await;
+                    }
+                }
+            }
+        }
+
+
+        /// Creates output buffer in split below to display
+        /// stderr of --exec command separately from its stdout.
+        /// Focus is returned back to previously active window
+        async fn create_stderr_buffer(&mut self) {
+            let stderr_buf_opts = self.stderr_buffer_commands();
+
+            let route_id = self.stderr_route_id();
+            self.stderr_rx = Some(self.nvim_conn.open_route(route_id));
+
+            let BufferActions {
+                outp_ctx,
+                nvim_conn: NeovimConnection { nvim_actions, initial_buf_number, .. },
+                ..
+            } = self;
+
+            let active_win = nvim_actions
+                .get_current_window()
+                .await
+                .expect("Cannot get currently active window to create stderr buffer");
+
+            let split_opts = crate::cli::SplitOptions::below();
+            let stderr_outp = nvim_actions
+                .create_split_output_buffer(&split_opts)
+                .await;
+
+            nvim_actions
+                .prepare_output_buffer(*initial_buf_number, stderr_buf_opts)
+                .await;
+
+            let mut buf_title = outp_ctx.opt.name
+                .clone()
+                .or_else(|| outp_ctx.source_cmd.clone())
+                .unwrap_or_default();
+            buf_title.push_str(" stderr");
+            nvim_actions
+                .update_buffer_title(&stderr_outp.buf, buf_title.trim_start())
+                .await;
+
+            if let Err(e) = nvim_actions
+                .switch_to_window(&active_win)
+                .await
+            {
+                log::error!(target: "stderr", "Cannot switch back to active window: {e}");
+            }
+
+            self.open_stderr_sink(stderr_outp);
+        }
+
+
+        /// Stderr buffer notifies page with its own route id
+        /// like buffers of named inputs do
+        fn stderr_route_id(&self) -> String {
+            format!("{}:stderr", self.outp_ctx.page_id)
+        }
+
+
+        fn stderr_buffer_commands(&self) -> OutputCommands {
+            OutputCommands::for_stderr_buffer(
+                &self.stderr_route_id(),
+                u128::from(self.channel),
+                &self.outp_ctx.opt.output
+            )
+        }
+
+
+        fn open_stderr_sink(&mut self, stderr_outp: OutputBuffer) {
+            let stderr_sink = std::fs::OpenOptions::new()
+                .append(true)
+                .open(&stderr_outp.pty_path)
+                .expect("Cannot open stderr PTY device");
+
+            self.stderr_sink = Some(Box::new(stderr_sink));
+            self.stderr_buf = Some(stderr_outp.buf);
+        }
+
+
+        /// Receives notification sent from output buffer or from stderr
+        /// buffer. Closing of stderr buffer isn't returned, since
+        /// its lines are displayed in output buffer afterwards
+        async fn recv_notification(&mut self) -> Option<NotificationFromNeovim> {
+            loop {
+                let Some(stderr_rx) = self.stderr_rx.as_mut() else {
+                    return self.nvim_conn.rx
+                        .recv()
+                        .await
+                };
+
+                tokio::select! {
+                    notification = self.nvim_conn.rx.recv() => return notification,
+
+                    notification = stderr_rx.recv() => match notification {
+                        Some(NotificationFromNeovim::BufferClosed) | None => {
+                            log::info!(target: "stderr", "Stderr buffer is closed");
+
+                            self.stderr_rx = None;
+                            self.stderr_sink = None;
+                            self.stderr_buf = None;
+                        }
+
+                        notification => return notification,
+                    },
+                }
+            }
+        }
+
+
+        /// Writes line into stderr buffer if it was opened.
+        /// Returns false when there's no stderr buffer or it was
+        /// closed, so line should be displayed in output buffer instead
+        fn display_stderr_line(&mut self, ln: &[u8]) -> bool {
+            let Some(stderr_sink) = self.stderr_sink.as_mut() else {
+                return false
+            };
+
+            if let Err(e) = stderr_sink.write_all(ln) {
+                log::info!(target: "stderr", "Stderr buffer is closed: {e}");
+
+                self.stderr_sink = None;
+
+                return false
+            }
+
+            true
+        }
+
+
+        /// Blocks until `:PageRerun` will be invoked on neovim side
+        /// or until watch interval elapses (if provided).
+        /// Exits when output buffer or neovim will be closed
+        async fn wait_for_rerun(&mut self, watch_interval: Option<std::time::Duration>) {
+            let rerun_at = watch_interval
+                .map(|interval| tokio::time::Instant::now() + interval);
+
+            loop {
+                let notification_future = self
+                    .recv_notification();
+
+                let notification = match rerun_at {
+                    Some(rerun_at) => {
+                        match tokio::time::timeout_at(rerun_at, notification_future)
+                            .await
+                        {
+                            Ok(n) => n,
+                            Err(_) => {
+                                log::info!(target: "output-state", "Watch interval elapsed");
+
+                                return
+                            }
+                        }
+                    }
+
+                    None => notification_future.await,
+                };
+
+                match notification {
+                    Some(NotificationFromNeovim::Rerun) => {
+                        log::info!(target: "output-state", "Rerun");
+
+                        return
+                    }
+
+                    Some(NotificationFromNeovim::Clear) => {
+                        log::info!(target: "output-state", "Clear");
+
+                        self.clear_content()
+                            .await;
+                    }
+
+                    Some(NotificationFromNeovim::BufferClosed) => {
+                        log::info!(target: "output-state", "Buffer closed");
+
+                        self.done()
+                            .await;
+                    }
+                    None => {
+                        log::info!(target: "output-state", "Neovim closed");
+
+                        self.done()
+                            .await;
+                    }
+
+                    Some(n) => {
+                        log::trace!(target: "output-state", "Skip notification: {n:?}");
+                    }
+                }
+            }
+        }
+
+
         /// Writes line to PTY device and gracefully handles failures:
         /// if error occurs then page waits for `page_buffer_closed`
         /// notification that's sent on `BufDelete` event and signals
@@ -1006,6 +2289,111 @@ mod output_buffer_usage {
             Ok(())
         }
 
+        /// Updates statistics of input that was read and publishes
+        /// them if enough time has been passed since last publishing
+        async fn line_has_been_read(&mut self, ln: &[u8]) {
+            self.progress
+                .line_has_been_read(ln.len());
+
+            self.publish_progress_if_needed()
+                .await;
+        }
+
+
+        /// Publishes statistics of input if enough time has been
+        /// passed since last publishing. Invoked on each tick of
+        /// publishing interval too, so they're updated when input stalls
+        async fn publish_progress_if_needed(&mut self) {
+            if self.progress.should_be_published() {
+                self.publish_progress()
+                    .await;
+            }
+        }
+
+
+        /// Sets `b:page_lines_read`, `b:page_bytes_read`, `b:page_eof`,
+        /// `b:page_rate`, `b:page_paused` and `b:page_source_pid` variables
+        /// on output buffer (and on stderr buffer if it's opened)
+        /// and then runs `PageProgress` autocommand
+        async fn publish_progress(&mut self) {
+            let progress = self.progress
+                .publish();
+
+            if let Some(ref stderr_buf) = self.stderr_buf {
+                self.nvim_conn.nvim_actions
+                    .publish_progress(stderr_buf, progress.clone())
+                    .await;
+            }
+
+            self.nvim_conn.nvim_actions
+                .publish_progress(&self.buf, progress)
+                .await;
+        }
+
+
+        /// Blocks until `:PageResume` will be invoked on neovim side.
+        /// Meanwhile the producer is blocked on write as soon as
+        /// pipe buffer becomes full
+        async fn wait_for_resume(&mut self) {
+            log::info!(target: "output-state", "Paused");
+
+            self.nvim_conn.nvim_actions
+                .notify_paused(true)
+                .await;
+
+            self.progress.paused = true;
+            self.publish_progress()
+                .await;
+
+            loop {
+                match self
+                    .recv_notification()
+                    .await
+                {
+                    Some(NotificationFromNeovim::Resume) => {
+                        log::info!(target: "output-state", "Resumed");
+
+                        self.nvim_conn.nvim_actions
+                            .notify_paused(false)
+                            .await;
+
+                        self.progress.paused = false;
+                        self.publish_progress()
+                            .await;
+
+                        return
+                    }
+
+                    Some(NotificationFromNeovim::Clear) => {
+                        log::info!(target: "output-state", "Clear");
+
+                        self.clear_content()
+                            .await;
+                        self.publish_progress()
+                            .await;
+                    }
+
+                    Some(NotificationFromNeovim::BufferClosed) => {
+                        log::info!(target: "output-state", "Buffer closed");
+
+                        self.done()
+                            .await;
+                    }
+                    None => {
+                        log::info!(target: "output-state", "Neovim closed");
+
+                        self.done()
+                            .await;
+                    }
+
+                    Some(n) => {
+                        log::trace!(target: "output-state", "Skip notification: {n:?}");
+                    }
+                }
+            }
+        }
+
+
         /// If there's more than -z value lines to read (default `90_000`)
         /// then output will be pagerized through spawning `page -p` and
         /// writing to it's PTY device
@@ -1066,32 +2454,67 @@ mod output_buffer_usage {
                 return
             }
 
+            self.publish_progress()
+                .await;
+
             self.nvim_conn.nvim_actions
                 .notify_query_finished(s.how_many_lines_was_sent())
                 .await;
 
-            match self.nvim_conn.rx
-                .recv()
-                .await
-            {
-                Some(NotificationFromNeovim::FetchLines(n)) =>
-                    s.next_part(n),
+            loop {
+                match self.nvim_conn.rx
+                    .recv()
+                    .await
+                {
+                    Some(NotificationFromNeovim::FetchLines(n)) =>
+                        s.next_part(n),
 
-                Some(NotificationFromNeovim::FetchPart) =>
-                    s.next_part(self.outp_ctx.query_lines_count),
+                    Some(NotificationFromNeovim::FetchPart) =>
+                        s.next_part(self.outp_ctx.query_lines_count),
 
-                Some(NotificationFromNeovim::BufferClosed) => {
-                    log::info!(target: "output-state", "Buffer closed");
+                    Some(NotificationFromNeovim::Clear) => {
+                        log::info!(target: "output-state", "Clear");
 
-                    self.done()
-                        .await;
-                }
-                None => {
-                    log::info!(target: "output-state", "Neovim closed");
+                        self.clear_content()
+                            .await;
 
-                    self.done()
-                        .await;
+                        continue
+                    }
+
+                    Some(NotificationFromNeovim::BufferClosed) => {
+                        log::info!(target: "output-state", "Buffer closed");
+
+                        self.done()
+                            .await;
+                    }
+                    None => {
+                        log::info!(target: "output-state", "Neovim closed");
+
+                        self.done()
+                            .await;
+                    }
+
+                    // Reading is already suspended until next query
+                    Some(n) => {
+                        log::trace!(target: "output-state", "Skip notification: {n:?}");
+
+                        continue
+                    }
                 }
+
+                break
+            }
+        }
+
+
+        /// Saves instance buffer after its writer disconnects,
+        /// to restore it by --restore-instances
+        pub async fn persist_instance_buffer(&mut self) {
+            use super::context::connect_neovim::InstanceUsage;
+            if let InstanceUsage::Enabled { name, .. } = &self.outp_ctx.inst_usage {
+                self.nvim_conn.nvim_actions
+                    .persist_instance_buffer(name)
+                    .await;
             }
         }
 
@@ -1149,10 +2572,24 @@ mod output_buffer_usage {
             }
         }
 
-        /// Closes neovim connection then exits with 0 status code
+        /// Waits until named inputs are read, then closes neovim connection
+        /// and exits with 0 status code or with status of executed command
+        /// if --exit-status is provided
         pub async fn done(&mut self) {
+            self.named_inputs
+                .wait()
+                .await;
+
+            if let Some(ref fifo_path) = self.outp_ctx.opt.fifo_listen {
+                fifo::remove(fifo_path.as_ref());
+            }
+
             log::trace!(target: "done", "now page can exit");
 
+            if self.outp_ctx.opt.output.exit_status {
+                connection::close_and_exit_with_status(self.nvim_conn, self.exit_status).await;
+            }
+
             connection::close_and_exit(self.nvim_conn).await;
         }
 
@@ -1164,7 +2601,7 @@ mod output_buffer_usage {
                     Box::new(
                         std::fs::OpenOptions::new()
                             .append(true)
-                            .open(&self.outp_ctx.buf_pty_path)
+                            .open(&self.buf_pty_path)
                             .expect("Cannot open PTY device")
                     )
                 })
@@ -1177,6 +2614,141 @@ mod output_buffer_usage {
 
     }
 
+    /// Compares lines of --exec command output with lines
+    /// from its previous run and highlights changed ones
+    struct Differences {
+        previous_lines: Vec<Vec<u8>>,
+        current_lines: Vec<Vec<u8>>,
+        is_first_run: bool,
+    }
+
+    impl Default for Differences {
+        fn default() -> Differences {
+            Differences {
+                previous_lines: vec![],
+                current_lines: vec![],
+                is_first_run: true,
+            }
+        }
+    }
+
+    impl Differences {
+        /// Previous run might have no output, then all lines are changed
+        fn highlight_changed<'b>(&mut self, ln: &'b [u8]) -> std::borrow::Cow<'b, [u8]> {
+            let i = self.current_lines.len();
+            self.current_lines.push(ln.to_vec());
+
+            let is_same = self.previous_lines
+                .get(i)
+                .map_or(false, |previous_ln| previous_ln == ln);
+            if self.is_first_run || is_same {
+                return std::borrow::Cow::from(ln)
+            }
+
+            const REVERSE: &[u8] = b"\x1B[7m";
+            const NO_REVERSE: &[u8] = b"\x1B[27m";
+
+            std::borrow::Cow::from(exec::wrap_with_sgr(ln, REVERSE, NO_REVERSE))
+        }
+
+
+        fn next_run(&mut self) {
+            self.previous_lines = std::mem::take(&mut self.current_lines);
+            self.is_first_run = false;
+        }
+    }
+
+
+    /// Collects statistics about input read by page which is
+    /// periodically published into output buffer variables
+    /// (to display progress in statusline)
+    struct Progress {
+        lines_read: usize,
+        bytes_read: usize,
+        eof: bool,
+        paused: bool,
+        source_pid: Option<u32>,
+        published_lines_read: usize,
+        published_rate: f64,
+        published_at: std::time::Instant,
+    }
+
+    impl Progress {
+        const PUBLISH_INTERVAL: std::time::Duration = std::time::Duration::from_millis(256);
+
+        fn new(source_pid: Option<u32>) -> Progress {
+            Progress {
+                lines_read: 0,
+                bytes_read: 0,
+                eof: false,
+                paused: false,
+                source_pid,
+                published_lines_read: 0,
+                published_rate: 0.0,
+                published_at: std::time::Instant::now(),
+            }
+        }
+
+
+        fn line_has_been_read(&mut self, bytes_count: usize) {
+            self.lines_read += 1;
+            self.bytes_read += bytes_count;
+        }
+
+
+        /// Progress is published when some lines were read since
+        /// last publishing and also once after input stalls, so
+        /// rate drops to zero. Otherwise there's nothing to update
+        fn should_be_published(&self) -> bool {
+            let is_changed = self.lines_read != self.published_lines_read ||
+                self.published_rate != 0.0;
+
+            is_changed && self.published_at.elapsed() >= Self::PUBLISH_INTERVAL
+        }
+
+
+        /// Returns interval on which progress should be checked
+        /// when lines aren't read
+        fn publishing_interval() -> tokio::time::Interval {
+            let mut interval = tokio::time::interval(Self::PUBLISH_INTERVAL);
+            interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+
+            interval
+        }
+
+
+        /// Returns variables that should be set on output buffer.
+        /// Rate is measured in lines per second since last publishing
+        fn publish(&mut self) -> Vec<(Value, Value)> {
+            let elapsed = self.published_at
+                .elapsed()
+                .as_secs_f64();
+
+            let mut rate = 0.0;
+            if elapsed > 0.0 {
+                rate = (self.lines_read - self.published_lines_read) as f64 / elapsed;
+            }
+
+            self.published_lines_read = self.lines_read;
+            self.published_rate = rate;
+            self.published_at = std::time::Instant::now();
+
+            let mut progress = vec![
+                (Value::from("page_lines_read"), Value::from(self.lines_read as u64)),
+                (Value::from("page_bytes_read"), Value::from(self.bytes_read as u64)),
+                (Value::from("page_eof"), Value::from(self.eof)),
+                (Value::from("page_rate"), Value::from(rate)),
+                (Value::from("page_paused"), Value::from(self.paused)),
+            ];
+            if let Some(pid) = self.source_pid {
+                progress.push((Value::from("page_source_pid"), Value::from(pid)));
+            }
+
+            progress
+        }
+    }
+
+
     /// Encapsulates state of querying lines from neovim side
     /// with :Page <count> command.
     /// Used only when -q <count> argument is provided
@@ -0,0 +1,112 @@
+//! A module that converts formatted man page read from page's stdin
+//! into plain text that's understood by man plugin of neovim (see --man)
+
+
+/// Removes overstrikes that `man` uses for bold (`c\bc`) and underline
+/// (`_\bc`) text together with escape sequences that it uses instead
+/// when $MAN_KEEP_FORMATTING or $GROFF_SGR are set
+pub fn strip_formatting(text: &str) -> String {
+    let text = crate::display_width::strip_escapes(text);
+    let mut plain = String::with_capacity(text.len());
+
+    for c in text.chars() {
+        if c == '\x08' {
+            plain.pop();
+        } else {
+            plain.push(c);
+        }
+    }
+
+    plain
+}
+
+
+/// Returns name and section of man page from $MAN_PN which is set
+/// by man-db or from its header line like `LS(1)  User Commands  LS(1)`
+pub fn man_page_title(lines: &[String]) -> Option<(String, String)> {
+    let man_pn = std::env::var("MAN_PN")
+        .ok();
+
+    man_page_title_with_env(lines, man_pn.as_deref())
+}
+
+
+/// Returns title of man page with provided value of $MAN_PN
+fn man_page_title_with_env(lines: &[String], man_pn: Option<&str>) -> Option<(String, String)> {
+    if let Some(man_pn) = man_pn {
+        if let Some(title) = split_title(man_pn) {
+            return Some(title)
+        }
+    }
+
+    let header = lines
+        .iter()
+        .find(|ln| !ln.trim().is_empty())?;
+
+    let (name, sect) = split_title(header.split_whitespace().next()?)?;
+
+    Some((name.to_lowercase(), sect.to_lowercase()))
+}
+
+
+fn split_title(title: &str) -> Option<(String, String)> {
+    let (name, sect) = title
+        .strip_suffix(')')?
+        .rsplit_once('(')?;
+
+    if name.is_empty() || sect.is_empty() {
+        return None
+    }
+
+    Some((String::from(name), String::from(sect)))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn strips_overstrikes_and_escapes() {
+        let cases = [
+            ("plain text", "plain text"),
+            ("N\x08NA\x08AM\x08ME\x08E", "NAME"),
+            ("_\x08f_\x08i_\x08l_\x08e", "file"),
+            ("_\x08b\x08b", "b"),
+            ("\x08leading", "leading"),
+            ("日\x08日本\x08本", "日本"),
+            ("\x1B[1mNAME\x1B[0m", "NAME"),
+            ("\x1B[4mfile\x1B[24m and \x1B[22mmore", "file and more"),
+        ];
+
+        for (text, expected) in cases {
+            assert_eq!(strip_formatting(text), expected, "{text:?}");
+        }
+    }
+
+
+    #[test]
+    fn finds_man_page_title() {
+        let title = |name: &str, sect: &str| Some((String::from(name), String::from(sect)));
+
+        let cases = [
+            (None, vec!["LS(1)  User Commands  LS(1)", "NAME"], title("ls", "1")),
+            (None, vec!["", "  GIT-LOG(1)  Git Manual  GIT-LOG(1)"], title("git-log", "1")),
+            (None, vec!["PRINTF(3P)  POSIX Programmer's Manual"], title("printf", "3p")),
+            (Some("ls(1)"), vec!["OTHER(8)  System Manager's Manual"], title("ls", "1")),
+            (Some("invalid"), vec!["MOUNT(8)  System Manager's Manual"], title("mount", "8")),
+            (None, vec!["NAME", "ls - list directory contents"], None),
+            (None, vec!["()"], None),
+            (None, vec![], None),
+        ];
+
+        for (man_pn, lines, expected) in cases {
+            let lines = lines
+                .into_iter()
+                .map(String::from)
+                .collect::<Vec<_>>();
+
+            assert_eq!(man_page_title_with_env(&lines, man_pn), expected, "{man_pn:?} {lines:?}");
+        }
+    }
+}
@@ -1,7 +1,7 @@
 /// A module that extends neovim api with methods required in page
 use nvim_rs::{neovim::Neovim, error::CallError, Buffer, Window, Value};
 use indoc::{indoc, formatdoc};
-use connection::IoWrite;
+use connection::{IoWrite, WindowTarget};
 use std::{path::PathBuf, convert::TryFrom};
 
 
@@ -9,6 +9,7 @@ use std::{path::PathBuf, convert::TryFrom};
 /// with methods required in page. Results returned from underlying
 /// Neovim methods are mostly unwrapped, since we anyway cannot provide
 /// any meaningful falback logic on call side
+#[derive(Clone)]
 pub struct Actions {
     nvim: Neovim<IoWrite>,
 }
@@ -27,29 +28,138 @@ impl Actions {
     }
 
 
+    pub async fn get_current_window(&mut self) -> Result<Window<IoWrite>, Box<CallError>> {
+        self.nvim
+            .get_current_win()
+            .await
+    }
+
+
     pub async fn create_replacing_output_buffer(&mut self) -> OutputBuffer {
         let cmd = indoc! {"
             local buf = vim.api.nvim_get_current_buf()
         "};
 
-        self.create_buffer(cmd)
+        self.create_buffer(cmd, vec![])
             .await
             .expect("Error when creating output buffer from current")
     }
 
 
-    pub async fn create_switching_output_buffer(&mut self) -> OutputBuffer {
+    /// Creates output buffer in the target window, or in the current one
+    /// if target couldn't be focused
+    pub async fn create_switching_output_buffer(&mut self, target: &WindowTarget) -> OutputBuffer {
+        if let Some(focus_cmd) = target.focus_command() {
+            if let Err(e) = self.nvim.exec_lua(&focus_cmd, vec![]).await {
+                log::error!(target: "window target", "Cannot focus {target:?}: {e}");
+            }
+        }
+
         let cmd = indoc! {"
             local buf = vim.api.nvim_create_buf(true, false)
             vim.api.nvim_set_current_buf(buf)
         "};
 
-        self.create_buffer(cmd)
+        self.create_buffer(cmd, vec![])
             .await
             .expect("Error when creating output buffer")
     }
 
 
+    /// Creates output buffer that's displayed instead of instance buffer
+    /// in all its windows, or in the current window if there's none
+    pub async fn create_instance_replacing_output_buffer(&mut self, inst_buf: &Buffer<IoWrite>) -> OutputBuffer {
+        let cmd = indoc! {"
+            local inst_buf = ...
+            local buf = vim.api.nvim_create_buf(true, false)
+            local wins = vim.fn.win_findbuf(inst_buf)
+            if #wins > 0 and not vim.tbl_contains(wins, vim.api.nvim_get_current_win()) then
+                vim.api.nvim_set_current_win(wins[1])
+            end
+            for _, win in ipairs(wins) do
+                vim.api.nvim_win_set_buf(win, buf)
+            end
+            vim.api.nvim_set_current_buf(buf)
+        "};
+
+        self.create_buffer(cmd, vec![inst_buf.get_value().clone()])
+            .await
+            .expect("Error when creating output buffer in place of instance")
+    }
+
+
+    /// Creates output buffer in place of provided one, which is deleted
+    /// then together with its terminal (so text that was in scrollback
+    /// is gone, unlike when screen is cleared). It's displayed in windows
+    /// of replaced buffer and focus is returned to previously active window
+    pub async fn recreate_output_buffer(
+        &mut self,
+        buf: &Buffer<IoWrite>,
+        initial_buf_nr: i64,
+        cmds: OutputCommands
+    ) -> OutputBuffer {
+        log::trace!(target: "recreate output", "{:?}", buf.get_value());
+
+        let active_win = self
+            .get_current_window()
+            .await
+            .expect("Cannot get currently active window to recreate output buffer");
+        let active_buf = self
+            .get_current_buffer()
+            .await
+            .expect("Cannot get currently active buffer to recreate output buffer");
+
+        // Buffer closing shouldn't be reported to page which recreates it,
+        // and instance is moved onto new buffer to not be forgotten
+        let cmd = indoc! {"
+            local replaced_buf = ...
+            local buf = vim.api.nvim_create_buf(true, false)
+            local wins = vim.fn.win_findbuf(replaced_buf)
+            if #wins > 0 and not vim.tbl_contains(wins, vim.api.nvim_get_current_win()) then
+                vim.api.nvim_set_current_win(wins[1])
+            end
+            for _, win in ipairs(wins) do
+                vim.api.nvim_win_set_buf(win, buf)
+            end
+            vim.api.nvim_set_current_buf(buf)
+            pcall(vim.api.nvim_clear_autocmds, { group = 'PageOutput', buffer = replaced_buf })
+            for _, inst in pairs(_G.page_instances or {}) do
+                if inst.buf == replaced_buf then
+                    inst.buf = buf
+                end
+            end
+        "};
+
+        let outp = self.create_buffer(cmd, vec![buf.get_value().clone()])
+            .await
+            .expect("Error when creating output buffer in place of other");
+
+        self.replace_instance_buffer(buf, &outp.buf)
+            .await;
+        self.prepare_output_buffer(initial_buf_nr, cmds)
+            .await;
+
+        if let Err(e) = self
+            .switch_to_window(&active_win)
+            .await
+        {
+            log::error!(target: "recreate output", "Cannot switch back to active window: {e}");
+        }
+
+        // New buffer is displayed in active window temporarily if replaced one is hidden
+        if active_buf.is_valid().await.unwrap_or(false) {
+            if let Err(e) = active_win
+                .set_buf(&active_buf)
+                .await
+            {
+                log::error!(target: "recreate output", "Cannot switch back to active buffer: {e}");
+            }
+        }
+
+        outp
+    }
+
+
     pub async fn create_split_output_buffer(
         &mut self,
         opt: &crate::cli::SplitOptions
@@ -158,7 +268,7 @@ impl Actions {
             "}
         };
 
-        self.create_buffer(&cmd)
+        self.create_buffer(&cmd, vec![])
             .await
             .expect("Error when creating split output buffer")
     }
@@ -166,7 +276,8 @@ impl Actions {
 
     async fn create_buffer(
         &mut self,
-         window_open_cmd: &str
+        window_open_cmd: &str,
+        args: Vec<Value>
     ) -> Result<OutputBuffer, String> {
         // Shell will be temporarily replaced with /bin/sleep to halt
         // for i32::MAX seconds or 68 years
@@ -174,6 +285,7 @@ impl Actions {
             local shell, shellcmdflag = vim.o.shell, vim.o.shellcmdflag
             vim.o.shell, vim.o.shellcmdflag = 'sleep', ''
             {window_open_cmd}
+            {remember_window}
             local chan = vim.api.nvim_call_function('termopen', {{ '2147483647' }})
             vim.o.shell, vim.o.shellcmdflag = shell, shellcmdflag
             local pty = vim.api.nvim_get_chan_info(chan).pty
@@ -181,11 +293,11 @@ impl Actions {
                 error 'No PTY on channel'
             end
             return {{ buf, pty }}
-        "};
+        ", remember_window = connection::REMEMBER_WINDOW};
         log::trace!(target: "create buffer", "{cmd}");
 
         let v = self.nvim
-            .exec_lua(&cmd, vec![])
+            .exec_lua(&cmd, args)
             .await
             .expect("Cannot create buffer");
 
@@ -193,6 +305,62 @@ impl Actions {
     }
 
 
+    /// Moves variables, options and name of instance buffer onto output buffer
+    /// created in its place, then deletes instance buffer with its terminal
+    /// (so text that was in scrollback is gone, unlike when screen is cleared)
+    pub async fn replace_instance_buffer(
+        &mut self,
+        inst_buf: &Buffer<IoWrite>,
+        buf: &Buffer<IoWrite>
+    ) {
+        log::trace!(target: "replace instance", "{:?}->{:?}", inst_buf.get_value(), buf.get_value());
+
+        let cmd = indoc! {"
+            local inst_buf, buf = ...
+            local skip_vars = {
+                changedtick = true,
+                term_title = true,
+                terminal_job_id = true,
+                terminal_job_pid = true,
+            }
+            for name, value in pairs(vim.fn.getbufvar(inst_buf, '')) do
+                if not skip_vars[name] then
+                    vim.api.nvim_buf_set_var(buf, name, value)
+                end
+            end
+            local skip_options = {
+                buftype = true,
+                channel = true,
+                modified = true,
+                modifiable = true,
+                readonly = true,
+            }
+            for name, info in pairs(vim.api.nvim_get_all_options_info()) do
+                if info.scope == 'buf' and not skip_options[name] then
+                    local value = vim.api.nvim_buf_get_option(inst_buf, name)
+                    if value ~= vim.api.nvim_buf_get_option(buf, name) then
+                        pcall(vim.api.nvim_buf_set_option, buf, name, value)
+                    end
+                end
+            end
+            local name = vim.api.nvim_buf_get_name(inst_buf)
+            vim.api.nvim_buf_delete(inst_buf, { force = true })
+            pcall(vim.api.nvim_buf_set_name, buf, name)
+        "};
+
+        let args = vec![
+            inst_buf.get_value().clone(),
+            buf.get_value().clone(),
+        ];
+        if let Err(e) = self.nvim
+            .exec_lua(cmd, args)
+            .await
+        {
+            log::error!(target: "replace instance", "Error when replacing instance buffer: {e}");
+        }
+    }
+
+
     pub async fn mark_buffer_as_instance(
         &mut self,
         buf: &Buffer<IoWrite>,
@@ -202,16 +370,161 @@ impl Actions {
         let bv = buf.get_value();
         log::trace!(target: "new instance", "{:?}->{inst_name}->{inst_pty_path}", bv);
 
-        let v = Value::from(vec![
+        // Instance is persisted on neovim exit and
+        // forgotten when its buffer is closed by user
+        let cmd = formatdoc! {"
+            local buf, inst_name, pty_path, persist_path = ...
+            {INSTANCES_REGISTRY}
+            vim.api.nvim_buf_set_var(buf, 'page_instance', {{ inst_name, pty_path }})
+            _G.page_instances[inst_name] = {{
+                buf = buf,
+                pty_path = pty_path,
+                persist_path = persist_path,
+            }}
+            vim.api.nvim_create_autocmd('BufDelete', {{
+                buffer = buf,
+                callback = function()
+                    local inst = _G.page_instances[inst_name]
+                    if inst and inst.buf == buf then
+                        _G.page_instances[inst_name] = nil
+                        if inst.persist_path and vim.v.exiting == vim.NIL then
+                            os.remove(inst.persist_path)
+                        end
+                    end
+                end
+            }})
+        "};
+
+        let persist_path = crate::persist::instance_state_path(inst_name)
+            .map_or(Value::Nil, |p| Value::from(p.to_string_lossy().as_ref()));
+
+        let args = vec![
+            bv.clone(),
             Value::from(inst_name),
-            Value::from(inst_pty_path)
-        ]);
+            Value::from(inst_pty_path),
+            persist_path,
+        ];
+        if let Err(e) = self.nvim
+            .exec_lua(&cmd, args)
+            .await
+        {
+            log::error!(target: "new instance", "Error when setting instance mark: {e}");
+        }
+    }
+
+
+    /// Stores channel and id of page process that writes into instance buffer,
+    /// so it's reported as connected while this channel is open
+    /// and could be asked to clear instance buffer
+    pub async fn register_instance_writer(&mut self, inst_name: &str, channel: u64, page_id: &str) {
+        log::trace!(target: "instance writer", "{inst_name}->{channel}:{page_id}");
+
+        let cmd = format!("inst.writer_channel, inst.writer_page_id = {channel}, arg");
+
+        if let Err(e) = self
+            .on_instance_with_arg(inst_name, &cmd, Value::from(page_id))
+            .await
+        {
+            log::error!(target: "instance writer", "Error registering writer: {e}");
+        }
+    }
+
+
+    /// Saves instance buffer into state directory,
+    /// since its writer is disconnected and it won't change soon
+    pub async fn persist_instance_buffer(&mut self, inst_name: &str) {
+        log::trace!(target: "persist instance", "{inst_name}");
+
+        self.load_runtime()
+            .await;
+
+        if let Err(e) = self
+            .on_instance(inst_name, "require('page').persist_instance(inst_name)")
+            .await
+        {
+            log::error!(target: "persist instance", "Cannot persist instance buffer: {e}");
+        }
+    }
+
+
+    pub async fn list_instances(&mut self) -> Vec<InstanceBuffer> {
+        log::trace!(target: "list instances", "");
+
+        let cmd = indoc! {"
+            local instances = {}
+            for inst_name, inst in pairs(_G.page_instances or {}) do
+                if vim.api.nvim_buf_is_valid(inst.buf) then
+                    local writer_connected = inst.writer_channel ~= nil and
+                        next(vim.api.nvim_get_chan_info(inst.writer_channel)) ~= nil
+                    table.insert(instances, {
+                        inst_name,
+                        inst.buf,
+                        inst.pty_path,
+                        vim.api.nvim_buf_line_count(inst.buf),
+                        writer_connected,
+                    })
+                end
+            end
+            table.sort(instances, function(a, b) return a[1] < b[1] end)
+            return instances
+        "};
+
+        let value = self.nvim
+            .exec_lua(cmd, vec![])
+            .await
+            .expect("Cannot list instance buffers");
+
+        let instances = value
+            .as_array()
+            .map(|instances| instances
+                .iter()
+                .filter_map(|inst| {
+                    let inst = InstanceBuffer::try_from(inst);
+                    if let Err(e) = &inst {
+                        log::error!(target: "list instances", "Wrong response: {e}");
+                    }
+
+                    inst.ok()
+                })
+                .collect()
+            );
+
+        instances.unwrap_or_default()
+    }
+
+
+    pub async fn close_all_instance_buffers(&mut self) {
+        log::trace!(target: "close instances", "");
+
+        let cmd = indoc! {"
+            for _, inst in pairs(_G.page_instances or {}) do
+                if vim.api.nvim_buf_is_valid(inst.buf) then
+                    vim.api.nvim_buf_delete(inst.buf, { force = true })
+                end
+            end
+        "};
+
+        if let Err(e) = self.nvim
+            .exec_lua(cmd, vec![])
+            .await
+        {
+            log::error!(target: "close instances", "Error closing instance buffers: {e}");
+        }
+    }
+
+
+    pub async fn mark_buffer_source(
+        &mut self,
+        buf: &Buffer<IoWrite>,
+        source_cmd: &str
+    ) {
+        log::trace!(target: "source cmd", "{:?}->{source_cmd}", buf.get_value());
 
         if let Err(e) = buf
-            .set_var("page_instance", v)
+            .set_var("page_source_cmd", Value::from(source_cmd))
             .await
         {
-            log::error!(target: "new instance", "Error when setting instance mark: {e}");
+            log::error!(target: "source cmd", "Error when setting source command: {e}");
         }
     }
 
@@ -244,7 +557,7 @@ impl Actions {
         log::trace!(target: "close instance", "{inst_name}");
 
         if let Err(e) = self
-            .on_instance(inst_name, "vim.api.nvim_buf_delete(buf, {{ force = true }})")
+            .on_instance(inst_name, "vim.api.nvim_buf_delete(buf, { force = true })")
             .await
         {
             log::error!(
@@ -279,26 +592,101 @@ impl Actions {
     }
 
 
+    pub async fn scroll_instance_buffer(&mut self, inst_name: &str, bottom: bool) {
+        log::trace!(target: "scroll instance", "{inst_name}: bottom={bottom}");
+
+        let cmd = indoc! {"
+            local line = arg and vim.api.nvim_buf_line_count(buf) or 1
+            for _, win in ipairs(vim.fn.win_findbuf(buf)) do
+                vim.api.nvim_win_set_cursor(win, { line, 0 })
+            end
+        "};
+
+        if let Err(e) = self
+            .on_instance_with_arg(inst_name, cmd, Value::from(bottom))
+            .await
+        {
+            log::error!(target: "scroll instance", "Cannot scroll instance buffer: {e}");
+        }
+    }
+
+
+    /// Asks page process that writes into instance buffer to clear it.
+    /// Returns false when there's no connected writer
+    pub async fn clear_instance_by_writer(&mut self, inst_name: &str) -> Result<bool, Box<CallError>> {
+        log::trace!(target: "clear instance", "{inst_name}");
+
+        let cmd = indoc! {"
+            local channel = inst.writer_channel
+            if channel and next(vim.api.nvim_get_chan_info(channel)) ~= nil then
+                return require('page').notify({ channel = channel, page_id = inst.writer_page_id }, 'page_clear')
+            end
+            return false
+        "};
+
+        self.on_instance(inst_name, cmd)
+            .await
+            .map(|cleared| cleared.as_bool() == Some(true))
+    }
+
+
+    pub async fn set_instance_filetype(&mut self, inst_name: &str, filetype: &str) {
+        log::trace!(target: "instance filetype", "{inst_name}: {filetype}");
+
+        if let Err(e) = self
+            .on_instance_with_arg(inst_name, "vim.bo[buf].filetype = arg", Value::from(filetype))
+            .await
+        {
+            log::error!(target: "instance filetype", "Cannot set filetype: {e}");
+        }
+    }
+
+
+    /// Writes lines of instance buffer into file,
+    /// skipping empty lines at the end of terminal
+    pub async fn save_instance_buffer(
+        &mut self,
+        inst_name: &str,
+        path: &str
+    ) -> Result<(), Box<CallError>> {
+        log::trace!(target: "save instance", "{inst_name}: {path}");
+
+        let cmd = indoc! {"
+            local lines = vim.api.nvim_buf_get_lines(buf, 0, -1, false)
+            while #lines > 0 and lines[#lines] == '' do
+                table.remove(lines)
+            end
+            if vim.fn.writefile(lines, arg) ~= 0 then
+                error('Cannot write ' .. arg)
+            end
+        "};
+
+        self.on_instance_with_arg(inst_name, cmd, Value::from(path))
+            .await
+            .map(|_| ())
+    }
+
+
     async fn on_instance(
         &mut self,
         inst_name: &str,
         action: &str
     ) -> Result<Value, Box<CallError>> {
-        let cmd = formatdoc! {"
-            for _, buf in ipairs(vim.api.nvim_list_bufs()) do
-                local inst_name, pty_path
-                local ok = pcall(function()
-                    local inst_val = vim.api.nvim_buf_get_var(buf, 'page_instance')
-                    inst_name, pty_path = unpack(inst_val)
-                end)
-                if ok and inst_name == '{inst_name}' then
-                    {action}
-                end
-            end
-        "};
+        self.on_instance_with_arg(inst_name, action, Value::Nil)
+            .await
+    }
+
+
+    async fn on_instance_with_arg(
+        &mut self,
+        inst_name: &str,
+        action: &str,
+        arg: Value
+    ) -> Result<Value, Box<CallError>> {
+        let (cmd, args) = instance_command(inst_name, action, arg);
 
         self.nvim
-            .exec_lua(&cmd, vec![])
+            .exec_lua(&cmd, args)
             .await
     }
 
@@ -351,6 +739,44 @@ impl Actions {
     }
 
 
+    /// Sends page runtime from `lua/page/init.lua` into `package.loaded`
+    /// unless runtime of the same version was already sent in this session.
+    /// Version is checked first to not send the whole runtime each time
+    pub async fn load_runtime(&mut self) {
+        log::trace!(target: "load runtime", "{PAGE_RUNTIME_VERSION}");
+
+        let check = indoc! {"
+            local version = ...
+            local loaded = package.loaded.page
+            return type(loaded) == 'table' and loaded.version == version
+        "};
+
+        let is_loaded = self.nvim
+            .exec_lua(check, vec![Value::from(PAGE_RUNTIME_VERSION)])
+            .await
+            .map(|v| v.as_bool() == Some(true));
+        match is_loaded {
+            Ok(true) => return,
+            Ok(false) => {}
+            Err(e) => log::warn!(target: "load runtime", "Cannot check page runtime: {e}"),
+        }
+
+        let cmd = indoc! {r#"
+            local runtime, version = ...
+            local page = assert(loadstring(runtime, '@page/init.lua'))()
+            page.version = version
+            package.loaded.page = page
+        "#};
+
+        if let Err(e) = self.nvim
+            .exec_lua(cmd, vec![Value::from(PAGE_RUNTIME), Value::from(PAGE_RUNTIME_VERSION)])
+            .await
+        {
+            log::error!(target: "load runtime", "Cannot load page runtime: {e}");
+        }
+    }
+
+
     pub async fn prepare_output_buffer(
         &mut self,
         initial_buf_nr: i64,
@@ -359,33 +785,21 @@ impl Actions {
         let OutputCommands {
             ft,
             edit,
-            notify_closed,
             pre,
             cmd_provided_by_user,
             lua_provided_by_user,
-            after
+            after,
+            args
         } = cmds;
 
+        self.load_runtime().await;
+
         let options = formatdoc! {r#"
-            vim.b.page_alternate_bufnr = {initial_buf_nr}
-            if vim.wo.scrolloff > 999 or vim.wo.scrolloff < 0 then
-                vim.g.page_scrolloff_backup = 0
-            else
-                vim.g.page_scrolloff_backup = vim.wo.scrolloff
-            end
-            vim.bo.scrollback, vim.wo.scrolloff, vim.wo.signcolumn, vim.wo.number =
-                100000, 999, 'no', false
+            local page_args = ...
+            local page = require('page')
+            page.setup_buffer({initial_buf_nr})
             {ft}
             {edit}
-            vim.api.nvim_create_autocmd('BufEnter', {{
-                buffer = 0,
-                callback = function() vim.wo.scrolloff = 999 end
-            }})
-            vim.api.nvim_create_autocmd('BufLeave', {{
-                buffer = 0,
-                callback = function() vim.wo.scrolloff = vim.g.page_scrolloff_backup end
-            }})
-            {notify_closed}
             {pre}
             vim.api.nvim_exec_autocmds('User', {{
                 pattern = 'PageOpen'
@@ -395,10 +809,10 @@ impl Actions {
             {cmd_provided_by_user}
             {after}
         "#};
-        log::trace!(target: "prepare output", "{options}");
+        log::trace!(target: "prepare output", "{options}\n{args:?}");
 
         if let Err(e) = self.nvim
-            .exec_lua(&options, vec![])
+            .exec_lua(&options, vec![Value::Map(args)])
             .await
         {
             log::error!(
@@ -498,6 +912,18 @@ impl Actions {
     }
 
 
+    pub async fn switch_to_window(
+        &mut self,
+        win: &Window<IoWrite>
+    ) -> Result<(), Box<CallError>> {
+        log::trace!(target: "set window", "{:?}", win.get_value());
+
+        self.nvim
+            .set_current_win(win)
+            .await
+    }
+
+
     pub async fn switch_to_buffer(
         &mut self,
         buf: &Buffer<IoWrite>
@@ -568,8 +994,111 @@ impl Actions {
     ) -> Result<(), Box<dyn std::error::Error>> {
         log::trace!(target: "open file", "{file_opt:?}");
 
+        let cmd = indoc! {"
+            local file_opt = ...
+            vim.cmd('edit ' .. vim.fn.fnameescape(file_opt))
+        "};
+
+        self.nvim
+            .exec_lua(cmd, vec![Value::from(file_opt)])
+            .await?;
+
+        Ok(())
+    }
+
+
+    /// Opens man page in plain buffer with `ft=man` titled like
+    /// buffers opened by `:Man` to make its references navigable
+    pub async fn open_man_buffer(
+        &mut self,
+        title: Option<(String, String)>,
+        lines: Vec<String>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        log::trace!(target: "open man", "{title:?}: {} lines", lines.len());
+
+        let cmd = indoc! {"
+            local name, sect, lines = ...
+            local buf = vim.api.nvim_create_buf(true, true)
+            vim.api.nvim_set_current_buf(buf)
+            vim.api.nvim_buf_set_lines(buf, 0, -1, false, lines)
+            vim.bo.modified = false
+            if name ~= vim.NIL then
+                vim.b.man_sect = sect
+                pcall(vim.api.nvim_buf_set_name, buf, 'man://' .. name .. '(' .. sect .. ')')
+            end
+            vim.bo.filetype = 'man'
+            vim.bo.modifiable = false
+        "};
+
+        let (name, sect) = title
+            .map_or((Value::Nil, Value::Nil), |(name, sect)| {
+                (Value::from(name), Value::from(sect))
+            });
+        let lines = lines
+            .into_iter()
+            .map(Value::from)
+            .collect();
+
         self.nvim
-            .command(&format!("e {}", file_opt))
+            .exec_lua(cmd, vec![name, sect, Value::Array(lines)])
+            .await?;
+
+        Ok(())
+    }
+
+
+    /// Opens plain buffer which text is appended into by
+    /// `append_plain_buffer_lines` instead of output buffer
+    pub async fn create_plain_buffer(&mut self) -> Result<i64, Box<dyn std::error::Error>> {
+        let cmd = indoc! {"
+            local buf = vim.api.nvim_create_buf(true, true)
+            vim.api.nvim_set_current_buf(buf)
+            return buf
+        "};
+
+        let buf_nr = self.nvim
+            .exec_lua(cmd, vec![])
+            .await?
+            .as_i64()
+            .ok_or("Buffer number expected")?;
+
+        log::trace!(target: "plain buffer", "{buf_nr}");
+
+        Ok(buf_nr)
+    }
+
+
+    /// Appends lines to plain buffer (replacing its
+    /// content if filetype is provided with the first lines)
+    pub async fn append_plain_buffer_lines(
+        &mut self,
+        buf_nr: i64,
+        lines: Vec<String>,
+        filetype: Option<&str>,
+    ) -> Result<(), Box<CallError>> {
+        let cmd = indoc! {"
+            local buf, lines, filetype = ...
+            local modifiable = vim.bo[buf].modifiable
+            vim.bo[buf].modifiable = true
+            if filetype ~= vim.NIL then
+                vim.api.nvim_buf_set_lines(buf, 0, -1, false, lines)
+                vim.bo[buf].filetype = filetype
+            else
+                vim.api.nvim_buf_set_lines(buf, -1, -1, false, lines)
+            end
+            vim.bo[buf].modifiable = modifiable
+            vim.bo[buf].modified = false
+        "};
+
+        let lines = lines
+            .into_iter()
+            .map(Value::from)
+            .collect();
+        let filetype = filetype
+            .map_or(Value::Nil, Value::from);
+
+        self.nvim
+            .exec_lua(cmd, vec![Value::from(buf_nr), Value::Array(lines), filetype])
             .await?;
 
         Ok(())
@@ -592,6 +1121,97 @@ impl Actions {
     }
 
 
+    pub async fn notify_paused(&mut self, paused: bool) {
+        log::trace!(target: "paused", "{paused}");
+
+        let state = if paused { "paused" } else { "resumed" };
+        let cmd = formatdoc! {"
+            vim.cmd 'redraw'
+            local msg = '-- [PAGE] {state} --'
+            vim.api.nvim_echo({{ {{ msg, 'Comment', }}, }}, false, {{}})
+        "};
+
+        if let Err(e) = self.nvim
+            .exec_lua(&cmd, vec![])
+            .await
+        {
+            log::error!(target: "paused", "Cannot notify {state}: {e}");
+        }
+    }
+
+
+    pub async fn publish_progress(
+        &mut self,
+        buf: &Buffer<IoWrite>,
+        progress: Vec<(Value, Value)>
+    ) {
+        log::trace!(target: "progress", "{progress:?}");
+
+        let cmd = indoc! {"
+            local buf, progress = ...
+            if not vim.api.nvim_buf_is_valid(buf) then
+                return
+            end
+            for key, value in pairs(progress) do
+                vim.api.nvim_buf_set_var(buf, key, value)
+            end
+            vim.api.nvim_exec_autocmds('User', {
+                pattern = 'PageProgress',
+                modeline = false,
+                data = { buf = buf },
+            })
+        "};
+
+        let args = vec![
+            buf.get_value().clone(),
+            Value::Map(progress)
+        ];
+        if let Err(e) = self.nvim
+            .exec_lua(cmd, args)
+            .await
+        {
+            log::error!(target: "progress", "Cannot publish progress: {e}");
+        }
+    }
+
+
+    pub async fn notify_exit_status(
+        &mut self,
+        buf: &Buffer<IoWrite>,
+        status: i32,
+        elapsed: std::time::Duration
+    ) {
+        log::trace!(target: "exit status", "{status} after {elapsed:?}");
+
+        let cmd = formatdoc! {"
+            local buf, status = ...
+            vim.cmd 'redraw'
+            local msg = '-- [PAGE] exited with status ' .. status .. ' after {elapsed:.1}s --'
+            local hl = status == 0 and 'Comment' or 'ErrorMsg'
+            vim.api.nvim_echo({{ {{ msg, hl, }}, }}, false, {{}})
+            if not vim.api.nvim_buf_is_valid(buf) then
+                return
+            end
+            vim.api.nvim_buf_set_var(buf, 'page_exit_status', status)
+            vim.api.nvim_exec_autocmds('User', {{
+                pattern = 'PageExit',
+                modeline = false,
+            }})
+        ", elapsed = elapsed.as_secs_f64()};
+
+        let args = vec![
+            buf.get_value().clone(),
+            Value::from(status)
+        ];
+        if let Err(e) = self.nvim
+            .exec_lua(&cmd, args)
+            .await
+        {
+            log::error!(target: "exit status", "Cannot notify exit status: {e}");
+        }
+    }
+
+
     pub async fn notify_end_of_input(&mut self) {
         log::trace!(target: "end input", "");
 
@@ -639,6 +1259,91 @@ impl Actions {
 }
 
 
+/// Lua module that defines behaviour of output buffers.
+/// It's available as `require('page')` after it was loaded
+pub const PAGE_RUNTIME: &str = include_str!("../../lua/page/init.lua");
+
+/// Runtime is reloaded only when page of other version connects
+const PAGE_RUNTIME_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+
+/// Registry of instance buffers stored in global table on neovim side
+/// (to not scan all buffers) together with `:PageInstances` picker
+const INSTANCES_REGISTRY: &str = indoc! {"
+    if not _G.page_instances then
+        _G.page_instances = {}
+        vim.api.nvim_create_user_command('PageInstances', function()
+            local inst_names = vim.tbl_keys(_G.page_instances)
+            table.sort(inst_names)
+            vim.ui.select(inst_names, {
+                prompt = 'Page instances',
+                format_item = function(inst_name)
+                    local inst = _G.page_instances[inst_name]
+                    return inst_name .. ' [' .. inst.buf .. '] ' .. inst.pty_path
+                end,
+            }, function(inst_name)
+                local inst = inst_name and _G.page_instances[inst_name]
+                if inst and vim.api.nvim_buf_is_valid(inst.buf) then
+                    vim.api.nvim_set_current_buf(inst.buf)
+                end
+            end)
+        end, { force = true })
+        vim.api.nvim_create_autocmd('VimLeavePre', {
+            callback = function()
+                local ok, page = pcall(require, 'page')
+                if ok and page.persist_instances then
+                    page.persist_instances()
+                end
+            end
+        })
+    end
+"};
+
+
+/// This struct holds instance buffer data reported by `page --list-instances`
+#[derive(Debug)]
+pub struct InstanceBuffer {
+    pub name: String,
+    pub buf_nr: i64,
+    pub pty_path: String,
+    pub line_count: i64,
+    pub writer_connected: bool,
+}
+
+impl TryFrom<&Value> for InstanceBuffer {
+    type Error = String;
+
+    fn try_from(val: &Value) -> Result<Self, Self::Error> {
+        let tup = val
+            .as_array()
+            .ok_or("Instance is not an array")?;
+        let field = |idx, field_name| tup
+            .get(idx)
+            .ok_or(format!("No {field_name}"));
+
+        Ok(InstanceBuffer {
+            name: field(0, "name")?
+                .as_str()
+                .ok_or("Name not a string")?
+                .to_string(),
+            buf_nr: field(1, "buffer number")?
+                .as_i64()
+                .ok_or("Buffer number not a number")?,
+            pty_path: field(2, "pty path")?
+                .as_str()
+                .ok_or("PTY not a string")?
+                .to_string(),
+            line_count: field(3, "line count")?
+                .as_i64()
+                .ok_or("Line count not a number")?,
+            writer_connected: field(4, "writer")?
+                .as_bool()
+                .ok_or("Writer not a boolean")?,
+        })
+    }
+}
+
+
 /// This struct holds output buffer together with path to its PTY
 pub struct OutputBuffer {
     pub buf: Buffer<IoWrite>,
@@ -671,16 +1376,52 @@ impl TryFrom<(Value, &Neovim<IoWrite>)> for OutputBuffer {
 }
 
 
+/// Returns lua code that runs action on instance buffer
+/// with `inst`, `buf`, `pty_path` and `arg` in scope, together
+/// with arguments that carry instance name and action argument
+fn instance_command(inst_name: &str, action: &str, arg: Value) -> (String, Vec<Value>) {
+    // Instances created by older page runtime that didn't keep registry
+    // are marked only by b:page_instance, so buffers are scanned then
+    let cmd = formatdoc! {"
+        local inst_name, arg = ...
+        local inst
+        if _G.page_instances then
+            inst = _G.page_instances[inst_name]
+            if inst and not vim.api.nvim_buf_is_valid(inst.buf) then
+                inst = nil
+            end
+        else
+            for _, b in ipairs(vim.api.nvim_list_bufs()) do
+                local ok, mark = pcall(vim.api.nvim_buf_get_var, b, 'page_instance')
+                if ok and type(mark) == 'table' and mark[1] == inst_name then
+                    inst = {{ buf = b, pty_path = mark[2] }}
+                    break
+                end
+            end
+        end
+        if inst then
+            local buf, pty_path = inst.buf, inst.pty_path
+            {action}
+        end
+    "};
+
+    (cmd, vec![Value::from(inst_name), arg])
+}
+
+
 /// This struct provides commands that
-/// would be run on output buffer after creation
+/// would be run on output buffer after creation.
+/// User provided strings aren't interpolated into commands
+/// but passed as `page_args` table to not break them
+#[derive(Clone)]
 pub struct OutputCommands {
     edit: String,
     ft: String,
-    notify_closed: String,
     pre: String,
     cmd_provided_by_user: String,
     lua_provided_by_user: String,
     after: String,
+    args: Vec<(Value, Value)>,
 }
 
 impl OutputCommands {
@@ -689,129 +1430,69 @@ impl OutputCommands {
         lua_provided_by_user: &str,
         writeable: bool,
     ) -> OutputCommands {
-        let mut cmd_provided_by_user = String::from(cmd_provided_by_user);
+        let mut args = vec![];
+
+        let mut cmd = String::new();
         if !cmd_provided_by_user.is_empty() {
-            cmd_provided_by_user = format!("vim.cmd [====[{cmd_provided_by_user}]====]");
+            cmd = String::from("vim.cmd(page_args.cmd_provided_by_user)");
+            args.push((
+                Value::from("cmd_provided_by_user"),
+                Value::from(cmd_provided_by_user)
+            ));
         }
-
-        let lua_provided_by_user = String::from(lua_provided_by_user);
+        let cmd_provided_by_user = cmd;
+
+        let mut lua = String::new();
+        if !lua_provided_by_user.is_empty() {
+            lua = String::from("assert(loadstring(page_args.lua_provided_by_user))()");
+            args.push((
+                Value::from("lua_provided_by_user"),
+                Value::from(lua_provided_by_user)
+            ));
+        }
+        let lua_provided_by_user = lua;
 
         let mut edit = String::new();
         if !writeable {
-            let cmd = indoc! {r#"
+            edit += indoc! {"
                 vim.bo.modifiable = false
-
-                local function page_echo_notification(message)
-                    vim.defer_fn(function()
-                        local msg = '-- [PAGE] ' .. message .. ' --'
-                        vim.api.nvim_echo({{ msg, 'Comment' }, }, false, {})
-                        vim.api.nvim_create_autocmd('CursorMoved', {
-                            buffer = 0,
-                            once = true,
-                            command = 'echo'
-                        })
-                    end, 64)
-                end
-
-                local function page_scroll_text_bound(top, message, movement)
-                    local row, col, search
-                    if top then
-                        row, col, search = 1, 1, { '\\S', 'c' }
-                    else
-                        row, col, search = 9999999999, 9999999999, { '\\S', 'bc' }
-                    end
-                    vim.api.nvim_call_function('cursor', { row, col })
-                    vim.api.nvim_call_function('search', search)
-                    if movement ~= nil then
-                        movement()
-                    end
-                    page_echo_notification(message)
-                end
-
-                local function page_scroll(top, message)
-                    vim.wo.scrolloff = 0
-                    local movement
-                    if top then
-                        local key = vim.api
-                            .nvim_replace_termcodes('z<CR>M', true, false, true)
-                        movement = function()
-                            vim.api.nvim_feedkeys(key, 'nx', false)
-                        end
-                    else
-                        movement = function()
-                            vim.api.nvim_feedkeys('z-M', 'nx', false)
-                        end
-                    end
-                    page_scroll_text_bound(top, message, movement)
-                    vim.wo.scrolloff = 999
-                end
-
-                local function page_close()
-                    local buf = vim.api.nvim_get_current_buf()
-                    if buf ~= vim.b.page_alternate_bufnr and
-                        vim.api.nvim_buf_is_loaded(vim.b.page_alternate_bufnr)
-                    then
-                        vim.api.nvim_set_current_buf(vim.b.page_alternate_bufnr)
-                    end
-                    vim.api.nvim_buf_delete(buf, { force = true })
-                    local exit = true
-                    for _, b in ipairs(vim.api.nvim_list_bufs()) do
-                        local bt = vim.api.nvim_buf_get_option(b, 'buftype')
-                        if bt == '' or
-                            bt == 'acwrite' or
-                            bt == 'terminal' or
-                            bt == 'prompt'
-                        then
-                            local bm = vim.api.nvim_buf_get_option(b, 'modified')
-                            if bm then
-                                exit = false
-                                break
-                            end
-                            local bl = vim.api.nvim_buf_get_lines(b, 0, -1, false)
-                            if #bl ~= 0 and bl[1] ~= '' and #bl > 1 then
-                                exit = false
-                                break
-                            end
-                        end
-                    end
-                    if exit then
-                        vim.cmd 'qa!'
-                    end
-                end
-
-                local function page_map(key, rhs)
-                    vim.keymap.set('n', key, rhs, { nowait = true, buffer = 0 })
-                end
-
-                page_map('I', function()
-                    page_scroll(true, 'in the beginning of scroll')
-                end)
-                page_map('A', function()
-                    page_scroll(false, 'at the end of scroll')
-                end)
-                page_map('i', function()
-                    page_scroll_text_bound(true, 'in the beginning')
-                end)
-                page_map('a', function()
-                    page_scroll_text_bound(false, 'at the end')
-                end)
-                page_map('q', page_close)
-                page_map('u', '<C-u>')
-                page_map('d', '<C-d>')
-                page_map('x', 'G')
-            "#};
-
-            edit += cmd;
+                page.setup_keymaps()
+            "};
         }
 
         OutputCommands {
             ft: String::new(),
             pre: String::new(),
             after: String::new(),
-            notify_closed: String::new(),
             edit,
             cmd_provided_by_user,
             lua_provided_by_user,
+            args,
+        }
+    }
+
+
+    /// Makes value available in commands as `page_args[key]`
+    fn add_arg(&mut self, key: &str, value: impl Into<Value>) {
+        self.args
+            .push((Value::from(key), value.into()));
+    }
+
+
+    /// Runs commands and sets search pattern translated
+    /// from flags of `less` (see --less-compat)
+    pub fn add_less_translation(&mut self, opt: &crate::cli::OutputOptions) {
+        if let Some(ref less_commands) = opt.less_commands {
+            self.after += "vim.cmd(page_args.less_commands)\n";
+            self.add_arg("less_commands", less_commands.as_str());
+        }
+
+        if let Some(ref less_pattern) = opt.less_pattern {
+            self.after += indoc! {"
+                vim.fn.setreg('/', page_args.less_pattern)
+                vim.o.hlsearch = true
+            "};
+            self.add_arg("less_pattern", less_pattern.as_str());
         }
     }
 
@@ -838,11 +1519,87 @@ impl OutputCommands {
     }
 
 
+    pub fn for_man_buffer(
+        cmd_provided_by_user: &str,
+        lua_provided_by_user: &str,
+        writeable: bool
+    ) -> OutputCommands {
+        Self::create_with(
+            cmd_provided_by_user,
+            lua_provided_by_user,
+            writeable
+        )
+    }
+
+
+    pub fn for_diff_buffer(
+        diff_root: &str,
+        opt: &crate::cli::OutputOptions
+    ) -> OutputCommands {
+        let cmd_provided_by_user = opt.command
+            .as_deref()
+            .unwrap_or_default();
+        let lua_provided_by_user = opt.lua
+            .as_deref()
+            .unwrap_or_default();
+
+        let mut cmds = Self::create_with(
+            cmd_provided_by_user,
+            lua_provided_by_user,
+            opt.writable
+        );
+
+        if !opt.writable {
+            // Hunk keys need `page_args` to resolve paths
+            cmds.edit = String::from(indoc! {"
+                vim.bo.modifiable = false
+                page.setup_keymaps(page_args)
+            "});
+        }
+
+        cmds.pre = String::from("page.setup_diff(page_args)");
+        cmds.add_arg("diff_root", diff_root);
+        cmds.add_less_translation(opt);
+        cmds
+    }
+
+
+    /// Stderr buffer notifies page with its own route id like named inputs do
+    pub fn for_stderr_buffer(
+        route_id: &str,
+        channel: u128,
+        opt: &crate::cli::OutputOptions
+    ) -> OutputCommands {
+        let mut cmds = Self::for_output_buffer(route_id, channel, 0, true, opt);
+
+        cmds.ft = String::from("vim.bo.filetype = 'pager'");
+        cmds
+    }
+
+
     pub fn for_output_buffer(
-        page_id: u128,
+        page_id: &str,
         channel: u128,
         query_lines_count: usize,
+        rerunnable: bool,
         opt: &crate::cli::OutputOptions
+    ) -> OutputCommands {
+        let pwd = std::env::var("PWD")
+            .ok();
+
+        Self::for_output_buffer_at(page_id, channel, query_lines_count, rerunnable, opt, pwd.as_deref())
+    }
+
+
+    /// Output buffer commands with provided working directory,
+    /// which is set at output buffer when -P is passed
+    fn for_output_buffer_at(
+        page_id: &str,
+        channel: u128,
+        query_lines_count: usize,
+        rerunnable: bool,
+        opt: &crate::cli::OutputOptions,
+        pwd: Option<&str>
     ) -> OutputCommands {
         let cmd_provided_by_user = opt.command
             .as_deref()
@@ -857,80 +1614,124 @@ impl OutputCommands {
             opt.writable
         );
 
-        let ft = &opt.filetype;
-        cmds.ft = format!("vim.bo.filetype = '{ft}'");
-
-        cmds.notify_closed = formatdoc! {r#"
-            vim.api.nvim_create_autocmd('BufDelete', {{
-                buffer = 0,
-                callback = function()
-                    pcall(function()
-                        vim.rpcnotify({channel}, 'page_buffer_closed', '{page_id}')
-                    end)
-                end
-            }})
-        "#};
+        cmds.ft = String::from("vim.bo.filetype = page_args.filetype");
+        cmds.add_arg("filetype", opt.filetype.as_str());
+        cmds.add_arg("page_id", page_id);
 
-        if query_lines_count != 0 {
-
-            let prefix = cmds.pre;
-            cmds.pre = formatdoc! {r#"
-                {prefix}
-                vim.b.page_query_size = {query_lines_count}
-                local function fetch_lines(opt)
-                    local ok = pcall(function()
-                        vim.rpcnotify({channel}, 'page_fetch_lines', '{page_id}', opt.args)
-                    end)
-                    if not ok then
-                        page_echo_notification 'closed'
-                    end
-                end
-                local function define_query_cmd()
-                    local cmd_opts = {{ force = true, nargs = '?' }}
-                    vim.api.nvim_create_user_command('Page', fetch_lines, cmd_opts)
-                end
-                define_query_cmd()
-                vim.api.nvim_create_autocmd('BufEnter', {{
-                    buffer = 0,
-                    callback = define_query_cmd,
-                }})
-            "#};
-
-            if !opt.writable {
-
-                let prefix = cmds.pre;
-                cmds.pre = formatdoc! {r#"
-                    {prefix}
-                    page_map('r', function()
-                        fetch_lines {{ args = vim.b.page_query_size * vim.v.count1 }}
-                    end)
-                    page_map('R', function()
-                        fetch_lines {{ args = 9999 }}
-                    end)
-                "#};
-            }
+        if !opt.writable {
+            // Output buffer keys also need `page_args` to reach page process
+            cmds.edit = String::from(indoc! {"
+                vim.bo.modifiable = false
+                page.setup_keymaps(page_args)
+            "});
         }
 
+        cmds.pre = String::from("page.setup_output(page_args)");
+        cmds.add_arg("channel", channel as u64);
+        cmds.add_arg("writable", opt.writable);
+        cmds.add_arg("query_lines_count", query_lines_count as u64);
+        cmds.add_arg("rerunnable", rerunnable);
+
         if opt.pwd {
-            let pwd = std::env::var("PWD")
-                .unwrap();
-
-            let prefix = cmds.pre;
-            cmds.pre = formatdoc! {r#"
-                {prefix}
-                vim.b.page_lcd_backup = getcwd()
-                vim.cmd 'lcd {pwd}'
-                vim.api.nvim_create_autocmd('BufEnter', {{
-                    buffer = 0,
-                    command = 'lcd {pwd}'
-                }})
-                vim.api.nvim_create_autocmd('BufLeave', {{
-                    buffer = 0,
-                    command = 'exe "lcd" . b:page_lcd_backup'
-                }})
-            "#};
+            let pwd = pwd
+                .expect("$PWD isn't set");
+            cmds.add_arg("pwd", pwd);
         }
 
+        cmds.add_less_translation(opt);
         cmds
     }
 }
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::Parser;
+
+    /// Strings that would break lua source or neovim command if interpolated
+    const HOSTILE: [&str; 4] = [
+        "it's'); os.exit(1) --",
+        "]====] os.exit(1) --[====[",
+        "line\nos.exit(1)",
+        "echo | call system('rm')",
+    ];
+
+
+    fn source(cmds: &OutputCommands) -> String {
+        [
+            &cmds.ft,
+            &cmds.edit,
+            &cmds.pre,
+            &cmds.lua_provided_by_user,
+            &cmds.cmd_provided_by_user,
+            &cmds.after,
+        ]
+            .map(String::as_str)
+            .concat()
+    }
+
+
+    fn has_arg(cmds: &OutputCommands, key: &str, value: &str) -> bool {
+        cmds.args
+            .iter()
+            .any(|(k, v)| k.as_str() == Some(key) && v.as_str() == Some(value))
+    }
+
+
+    fn output_options(hostile: &str) -> crate::cli::OutputOptions {
+        crate::cli::Options::try_parse_from([
+            "page",
+            "-e", hostile,
+            "--e", hostile,
+            "-t", hostile,
+            "-P",
+            "--less-commands", hostile,
+            "--less-pattern", hostile,
+        ])
+            .expect("Cannot parse options")
+            .output
+    }
+
+
+    #[test]
+    fn create_with_passes_user_commands_as_args() {
+        for hostile in HOSTILE {
+            let cmds = OutputCommands::create_with(hostile, hostile, false);
+
+            assert!(!source(&cmds).contains(hostile), "{hostile:?}");
+            assert!(has_arg(&cmds, "cmd_provided_by_user", hostile));
+            assert!(has_arg(&cmds, "lua_provided_by_user", hostile));
+        }
+    }
+
+
+    #[test]
+    fn for_output_buffer_passes_names_and_pwd_as_args() {
+        for hostile in HOSTILE {
+            let opt = output_options(hostile);
+            let cmds = OutputCommands::for_output_buffer_at(hostile, 1, 0, true, &opt, Some(hostile));
+
+            assert!(!source(&cmds).contains(hostile), "{hostile:?}");
+            for key in ["page_id", "filetype", "pwd", "less_commands", "less_pattern"] {
+                assert!(has_arg(&cmds, key, hostile), "{key}: {hostile:?}");
+            }
+
+            let cmds = OutputCommands::for_diff_buffer(hostile, &opt);
+
+            assert!(!source(&cmds).contains(hostile), "{hostile:?}");
+            assert!(has_arg(&cmds, "diff_root", hostile));
+        }
+    }
+
+
+    #[test]
+    fn on_instance_passes_instance_name_as_arg() {
+        for hostile in HOSTILE {
+            let (cmd, args) = instance_command(hostile, "return buf", Value::from(hostile));
+
+            assert!(!cmd.contains(hostile), "{hostile:?}");
+            assert_eq!(args, vec![Value::from(hostile), Value::from(hostile)]);
+        }
+    }
+}
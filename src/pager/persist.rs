@@ -0,0 +1,99 @@
+//! A module that reads instance buffers which page's runtime persists into
+//! state directory, to recreate them after neovim restart (see --restore-instances)
+use std::path::PathBuf;
+
+
+/// Content and metadata of instance buffer saved on neovim side
+/// when its writer disconnects or neovim exits
+#[derive(Debug)]
+pub struct PersistedInstance {
+    pub name: String,
+    pub title: String,
+    pub filetype: String,
+    pub source_cmd: Option<String>,
+    pub lines: Vec<String>,
+}
+
+
+/// Returns path of file where instance buffer is persisted:
+/// $XDG_STATE_HOME/page/instances/<INSTANCE>.json
+pub fn instance_state_path(inst_name: &str) -> Option<PathBuf> {
+    // Instance name might contain path separators
+    let file_name = inst_name
+        .replace('%', "%25")
+        .replace('/', "%2F");
+
+    instances_dir()
+        .map(|instances_dir| instances_dir.join(format!("{file_name}.json")))
+}
+
+
+fn instances_dir() -> Option<PathBuf> {
+    connection::state_dir()
+        .map(|state_dir| state_dir.join("instances"))
+}
+
+
+/// Reads all persisted instance buffers sorted by name
+pub fn read_instances() -> Vec<PersistedInstance> {
+    let Some(instances_dir) = instances_dir() else {
+        return vec![]
+    };
+
+    let entries = match std::fs::read_dir(&instances_dir) {
+        Ok(entries) => entries,
+
+        Err(e) => {
+            if e.kind() != std::io::ErrorKind::NotFound {
+                log::warn!(target: "persist", "Cannot read {instances_dir:?}: {e}");
+            }
+
+            return vec![]
+        }
+    };
+
+    let mut instances = entries
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().map_or(false, |ext| ext == "json"))
+        .filter_map(|path| {
+            let inst = read_instance(&path);
+            if inst.is_none() {
+                log::warn!(target: "persist", "Cannot restore instance from {path:?}");
+            }
+
+            inst
+        })
+        .collect::<Vec<_>>();
+
+    instances.sort_by(|a, b| a.name.cmp(&b.name));
+    instances
+}
+
+
+fn read_instance(path: &std::path::Path) -> Option<PersistedInstance> {
+    let content = std::fs::read_to_string(path)
+        .ok()?;
+    let state = serde_json::from_str::<serde_json::Value>(&content)
+        .ok()?;
+
+    let string = |key| state
+        .get(key)
+        .and_then(serde_json::Value::as_str)
+        .map(String::from);
+
+    let lines = state
+        .get("lines")?
+        .as_array()?
+        .iter()
+        .filter_map(|ln| ln.as_str().map(String::from))
+        .collect();
+
+    Some(PersistedInstance {
+        name: string("name")?,
+        title: string("title").unwrap_or_default(),
+        filetype: string("filetype").unwrap_or_default(),
+        source_cmd: string("source_cmd"),
+        lines,
+    })
+}
@@ -0,0 +1,87 @@
+//! A module that renders text printed by -O with highlighting of headless
+//! neovim which loads user's config and colorscheme (see --nvim-render)
+use std::{io::Write, process::{Command, Stdio}};
+
+
+/// Loads page's runtime and exits with error status if rendering fails,
+/// so page could fall back to other highlighter
+const RENDER_CMD: &str = "lua \
+    local page = loadstring(vim.env.PAGE_RUNTIME)() \
+    package.loaded.page = page \
+    local ok, e = pcall(page.render, vim.env.PAGE_RENDER_FILETYPE, vim.env.PAGE_RENDER_PATH) \
+    if ok then vim.cmd 'qall!' else io.stderr:write(tostring(e)) vim.cmd 'cquit' end";
+
+
+/// Options of headless neovim process
+pub struct NeovimRender {
+    pub config: Option<String>,
+    pub path: Option<String>,
+}
+
+impl NeovimRender {
+    /// Returns text with neovim highlighting converted into escape sequences
+    /// or None if neovim couldn't be spawned or failed to render it
+    pub fn render(self, text: &[u8], filetype: &str) -> Option<Vec<u8>> {
+        let mut nvim = Command::new("nvim");
+        nvim.args(["--headless", "-n", "-i", "NONE"]);
+
+        if let Some(config) = self.config.or_else(connection::default_config_path) {
+            nvim.arg("-u").arg(config);
+        }
+
+        nvim.arg("-")
+            .arg("-c")
+            .arg(RENDER_CMD)
+            .env("PAGE_RUNTIME", crate::neovim::PAGE_RUNTIME)
+            .env("PAGE_RENDER_FILETYPE", filetype)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+
+        if let Some(path) = self.path {
+            nvim.env("PAGE_RENDER_PATH", path);
+        }
+
+        let mut nvim_proc = match nvim.spawn() {
+            Ok(proc) => proc,
+
+            Err(e) => {
+                log::warn!(target: "render", "Cannot spawn neovim: {e}");
+
+                return None
+            }
+        };
+
+        // Neovim reads all text before it writes anything
+        let write = nvim_proc.stdin
+            .take()
+            .expect("Cannot get neovim stdin")
+            .write_all(text);
+
+        if let Err(e) = write {
+            log::warn!(target: "render", "Cannot write text: {e}");
+        }
+
+        let out = match nvim_proc.wait_with_output() {
+            Ok(out) => out,
+
+            Err(e) => {
+                log::warn!(target: "render", "Neovim ended unexpectedly: {e}");
+
+                return None
+            }
+        };
+
+        if !out.status.success() || out.stdout.is_empty() {
+            log::warn!(
+                target: "render", "Cannot render: {}: {}",
+                out.status,
+                String::from_utf8_lossy(&out.stderr)
+            );
+
+            return None
+        }
+
+        Some(out.stdout)
+    }
+}
@@ -164,10 +164,26 @@ pub struct SplitOptions {
     pub split_below_rows: Option<u8>,
 
     /// With any of -r -l -u -d -R -L -U -D open floating window instead of split
-    /// [to not overwrite data in the current terminal] {n}
-    /// ~ ~ ~
+    /// [to not overwrite data in the current terminal]
     #[clap(display_order=908, short='+')]
     pub popup: bool,
+
+    /// Open in a new tab instead of the current window
+    #[clap(display_order=909, long="tab")]
+    pub tab: bool,
+
+    /// Open in window with <WINDOW> number in the current tab or with <WINDOW> id
+    #[clap(display_order=910, long="window")]
+    pub window: Option<i64>,
+
+    /// Open in window used previously by page or nv if it's in the current tab
+    #[clap(display_order=911, long="window-reuse")]
+    pub window_reuse: bool,
+
+    /// Open in window returned by lua function which name is set in g:page_window_target {n}
+    /// ~ ~ ~
+    #[clap(display_order=912, long="window-target")]
+    pub window_target: bool,
 }
 
 
@@ -204,6 +220,12 @@ fn splits_arg_group() -> ArgGroup {
             "split_above_rows",
             "split_below_rows"
         ])
+        .args([
+            "tab",
+            "window",
+            "window_reuse",
+            "window_target"
+        ])
         .multiple(false)
 }
 
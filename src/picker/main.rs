@@ -38,6 +38,14 @@ mod main {
                 if address (-a or $NVIM) isn't set"
             );
         }
+        let split = &opt.split;
+        if split.tab || split.window.is_some() || split.window_reuse || split.window_target {
+            log::warn!(
+                target: "usage",
+                "Window target (--tab --window --window-reuse --window-target) is ignored \
+                if address (-a or $NVIM) isn't set"
+            );
+        }
     }
 }
 
@@ -111,6 +119,30 @@ async fn split_current_buffer(env_ctx: context::Env, conn: NeovimConnection) {
             .exec_lua(&cmd, vec![])
             .await
             .expect("Cannot create split window");
+
+    } else if env_ctx.opt.address.is_some() {
+        let split = &env_ctx.opt.split;
+        let target = connection::WindowTarget::new(
+            split.tab,
+            split.window,
+            split.window_reuse,
+            split.window_target
+        );
+
+        log::info!(target: "split_current_buffer", "{target:?}");
+
+        if let Some(focus_cmd) = target.focus_command() {
+            if let Err(e) = conn.nvim_actions.exec_lua(&focus_cmd, vec![]).await {
+                log::error!(target: "split_current_buffer", "Cannot focus {target:?}: {e}");
+            }
+        }
+    }
+
+    if let Err(e) = conn.nvim_actions
+        .exec_lua(connection::REMEMBER_WINDOW, vec![])
+        .await
+    {
+        log::error!(target: "split_current_buffer", "Cannot remember window: {e}");
     }
 
     read_stdin(env_ctx, conn).await;